@@ -0,0 +1,82 @@
+/// Turns the variable time between frames into a whole number of fixed
+/// simulation steps.
+pub struct FixedClock {
+    step: f32,
+    max_frame_time: f32,
+    accumulator: f32,
+}
+
+impl FixedClock {
+    pub fn new(step: f32, max_frame_time: f32) -> Self {
+        FixedClock {
+            step,
+            max_frame_time,
+            accumulator: 0.,
+        }
+    }
+
+    pub fn step(&self) -> f32 {
+        self.step
+    }
+
+    /// Adds a frame's time and returns how many steps to run. Long frames
+    /// are capped so a stall doesn't turn into a burst of catch-up steps.
+    pub fn advance(&mut self, frame_time: f32) -> u32 {
+        self.accumulator += frame_time.min(self.max_frame_time);
+
+        let steps = (self.accumulator / self.step) as u32;
+        self.accumulator -= steps as f32 * self.step;
+
+        steps
+    }
+
+    /// How far the next step is, from 0 to 1, to blend positions with.
+    pub fn alpha(&self) -> f32 {
+        self.accumulator / self.step
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Times that add up exactly in floats, so the counts are exact too.
+    const STEP: f32 = 1. / 8.;
+    const MAX_FRAME_TIME: f32 = 1. / 2.;
+
+    #[test]
+    fn runs_a_step_for_every_whole_step_of_time() {
+        let mut clock = FixedClock::new(STEP, MAX_FRAME_TIME);
+
+        assert_eq!(clock.advance(3. / 8.), 3);
+        assert_eq!(clock.advance(1. / 16.), 0);
+        assert_eq!(clock.advance(1. / 16.), 1);
+        assert_eq!(clock.advance(5. / 16.), 2);
+        assert_eq!(clock.alpha(), 0.5);
+    }
+
+    #[test]
+    fn drops_the_time_past_the_cap() {
+        let mut clock = FixedClock::new(STEP, MAX_FRAME_TIME);
+
+        assert_eq!(clock.advance(5.), 4);
+        assert_eq!(clock.alpha(), 0.);
+
+        assert_eq!(clock.advance(1. / 16.), 0);
+        assert_eq!(clock.advance(10. + 1. / 16.), 4);
+        assert_eq!(clock.alpha(), 0.5);
+    }
+
+    #[test]
+    fn alpha_stays_below_a_whole_step() {
+        let mut clock = FixedClock::new(1. / 120., 0.25);
+        let frames = [1. / 60., 1. / 144., 0.013, 0.1, 1. / 120., 0.9, 0.0021, 1. / 30.];
+
+        for frame_time in frames.iter().cycle().take(1000) {
+            clock.advance(*frame_time);
+
+            let alpha = clock.alpha();
+            assert!((0. ..1.).contains(&alpha), "alpha {alpha}");
+        }
+    }
+}
@@ -0,0 +1,220 @@
+//! Evolves brains that fly the bird, without opening a window:
+//!
+//! ```text
+//! cargo run --release --no-default-features --bin train -- [course|endless] [generations] [population]
+//! ```
+//!
+//! Every brain flies the same tracks every generation, spread over all the
+//! cores, so brains from different generations can be compared. The best
+//! brain so far is saved to `assets/genome.ron`, where the game picks it up
+//! as its AI player.
+use std::{env, fs, thread};
+
+use flappy_bird::{
+    brain::{Brain, GENOME},
+    course::Course,
+    evolution::Population,
+    physics::Physics,
+    run::{Run, State, STEP},
+    track::{Mode, Track},
+};
+
+const USAGE: &str = "usage: train [course|endless] [generations] [population]";
+/// Two minutes of flying. A brain that gets there is good enough.
+const MAX_TICKS: u32 = 120 * 120;
+/// Extra fitness for every pipe passed, on top of a point per tick.
+const PIPE_BONUS: f32 = 100.;
+const SEED: u64 = 1;
+/// Endless tracks every brain flies, from `SEED` on. Enough of them that a
+/// brain can't get by on learning one set of pipes by heart.
+const ENDLESS_SEEDS: u64 = 5;
+
+struct Options {
+    mode: Mode,
+    generations: u32,
+    population: usize,
+}
+
+impl Options {
+    fn parse() -> Self {
+        let mut options = Options {
+            mode: Mode::Course,
+            generations: 200,
+            population: 300,
+        };
+
+        let mut numbers = 0;
+
+        for arg in env::args().skip(1) {
+            match arg.as_str() {
+                "course" => options.mode = Mode::Course,
+                "endless" => options.mode = Mode::Endless,
+                _ => {
+                    let number: usize = arg.parse().expect(USAGE);
+
+                    match numbers {
+                        0 => options.generations = number as u32,
+                        1 => options.population = number,
+                        _ => panic!("{USAGE}"),
+                    }
+
+                    numbers += 1;
+                }
+            }
+        }
+
+        // Evolution needs parents to pick from.
+        if options.population < 2 {
+            panic!("{USAGE}\nthe population needs at least 2 birds");
+        }
+
+        options
+    }
+}
+
+/// How far a brain got.
+#[derive(Clone, Copy)]
+struct Flight {
+    ticks: u32,
+    score: u32,
+    finished: bool,
+}
+
+impl Flight {
+    fn fitness(&self) -> f32 {
+        self.ticks as f32 + self.score as f32 * PIPE_BONUS
+    }
+
+    fn made_it(&self) -> bool {
+        self.finished || self.ticks >= MAX_TICKS
+    }
+}
+
+/// How a brain did on every training track.
+struct Trial {
+    flights: Vec<Flight>,
+}
+
+impl Trial {
+    fn fitness(&self) -> f32 {
+        self.flights.iter().map(Flight::fitness).sum()
+    }
+
+    fn score(&self) -> u32 {
+        self.flights.iter().map(|flight| flight.score).sum()
+    }
+
+    fn made_it(&self) -> bool {
+        self.flights.iter().all(Flight::made_it)
+    }
+}
+
+fn fly(brain: &Brain, track: Track, physics: &Physics) -> Flight {
+    let mut run = Run::new(track, physics);
+
+    while run.is_playing() && run.tick < MAX_TICKS {
+        let flap = brain.wants_flap(&run);
+        run.step(flap, physics, STEP);
+    }
+
+    Flight {
+        ticks: run.tick,
+        score: run.score,
+        finished: run.state == State::Finished,
+    }
+}
+
+/// The seeds of the tracks every brain flies. The course is the same
+/// whatever the seed.
+fn seeds(mode: Mode) -> Vec<u64> {
+    match mode {
+        Mode::Course => vec![SEED],
+        Mode::Endless => (SEED..SEED + ENDLESS_SEEDS).collect(),
+    }
+}
+
+/// Flies every brain on the tracks `seeds` give, a share of them per core.
+fn fly_all(brains: &[Brain], mode: Mode, seeds: &[u64], course: &Course, physics: &Physics) -> Vec<Trial> {
+    let cores = thread::available_parallelism().map_or(1, |n| n.get());
+    let share = brains.len().div_ceil(cores).max(1);
+
+    thread::scope(|scope| {
+        let workers: Vec<_> = brains
+            .chunks(share)
+            .map(|chunk| scope.spawn(move || {
+                chunk
+                    .iter()
+                    .map(|brain| Trial {
+                        flights: seeds
+                            .iter()
+                            .map(|&seed| fly(brain, Track::new(mode, seed, course), physics))
+                            .collect(),
+                    })
+                    .collect::<Vec<_>>()
+            }))
+            .collect();
+
+        workers
+            .into_iter()
+            .flat_map(|worker| worker.join().unwrap())
+            .collect()
+    })
+}
+
+/// Saves `brain` with how it flew each track as a comment on top.
+fn save(brain: &Brain, generation: u32, mode: Mode, seeds: &[u64], trial: &Trial) {
+    let mut genome = format!("// Generation {generation} on the {} track:\n", mode.name().to_lowercase());
+
+    for (seed, flight) in seeds.iter().zip(&trial.flights) {
+        let made_it = if flight.made_it() { ", made it" } else { "" };
+        genome += &format!("//   seed {seed}: {} pipes in {} ticks{made_it}\n", flight.score, flight.ticks);
+    }
+
+    genome += &brain.to_ron();
+    genome += "\n";
+
+    fs::write(GENOME, genome).unwrap();
+}
+
+fn main() {
+    let options = Options::parse();
+    let physics = Physics::from_ron(&fs::read_to_string("./assets/physics.ron").unwrap());
+    let course = Course::from_json(&fs::read_to_string("./assets/map.json").unwrap());
+    let seeds = seeds(options.mode);
+
+    let mut population = Population::new(options.population, SEED);
+    let mut best = f32::MIN;
+
+    println!("Training {} birds for {} generations on the {} track", options.population, options.generations, options.mode.name().to_lowercase());
+
+    for _ in 0..options.generations {
+        let generation = population.generation;
+
+        let trials = fly_all(&population.brains, options.mode, &seeds, &course, &physics);
+        let fitness: Vec<f32> = trials.iter().map(Trial::fitness).collect();
+
+        let (top, trial) = trials
+            .iter()
+            .enumerate()
+            .max_by(|(_, a), (_, b)| a.fitness().total_cmp(&b.fitness()))
+            .unwrap();
+
+        let mean = fitness.iter().sum::<f32>() / fitness.len() as f32;
+        println!("Generation {generation:>4}: best {:>4} pipes, fitness {:.0}, mean fitness {mean:.0}", trial.score(), trial.fitness());
+
+        // Every generation flies the same tracks, so this is a fair race.
+        if trial.fitness() > best {
+            best = trial.fitness();
+            save(&population.brains[top], generation, options.mode, &seeds, trial);
+        }
+
+        if trial.made_it() {
+            println!("Generation {generation} made it, saved to {GENOME}");
+            return;
+        }
+
+        population.evolve(&fitness);
+    }
+
+    println!("Out of generations, the best brain is in {GENOME}");
+}
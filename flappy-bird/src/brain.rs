@@ -0,0 +1,144 @@
+use macroquad::{file::load_string, rand::RandGenerator};
+use nanoserde::{DeRon, SerRon};
+
+use crate::run::{Run, PLAYER_SIZE};
+
+/// Where the trainer saves the best brain and the game looks for it.
+pub const GENOME: &str = "./assets/genome.ron";
+
+/// What the bird sees: how far ahead the next gap ends, how far above or
+/// below its middle the bird is, and how fast it's falling.
+pub const INPUTS: usize = 3;
+const HIDDEN: usize = 6;
+/// A weight per input and a bias for every hidden neuron, then the same
+/// for the output.
+pub const GENES: usize = (INPUTS + 1) * HIDDEN + HIDDEN + 1;
+
+/// The inputs are divided by these to keep them around -1 to 1.
+const DISTANCE_SCALE: f32 = 256.;
+const SPEED_SCALE: f32 = 200.;
+
+/// A tiny neural net that decides when to flap. Its genes are all of its
+/// weights, so they're what evolves and what gets saved.
+#[derive(DeRon, SerRon, Clone, Debug)]
+pub struct Brain {
+    pub genes: Vec<f32>,
+}
+
+impl Brain {
+    pub fn random(rng: &RandGenerator) -> Self {
+        Brain {
+            genes: (0..GENES).map(|_| rng.gen_range(-1., 1.)).collect(),
+        }
+    }
+
+    /// The trained brain, if there is one.
+    pub async fn load() -> Option<Self> {
+        let genome = load_string(GENOME).await.ok()?;
+        Brain::from_ron(&genome)
+    }
+
+    pub fn from_ron(genome: &str) -> Option<Self> {
+        let brain: Brain = match DeRon::deserialize_ron(genome) {
+            Ok(brain) => brain,
+            Err(err) => {
+                println!("Skipping {GENOME}: {err}");
+                return None;
+            }
+        };
+
+        if brain.genes.len() != GENES {
+            println!("Skipping {GENOME}: {} genes instead of {GENES}", brain.genes.len());
+            return None;
+        }
+
+        Some(brain)
+    }
+
+    pub fn to_ron(&self) -> String {
+        SerRon::serialize_ron(self)
+    }
+
+    /// Only while falling, the way a player taps, rather than holding the
+    /// bird up with a flap every step.
+    pub fn wants_flap(&self, run: &Run) -> bool {
+        run.speed().y >= 0. && self.think(Brain::senses(run)) > 0.
+    }
+
+    fn senses(run: &Run) -> [f32; INPUTS] {
+        let pos = run.pos();
+        let middle = pos.y + PLAYER_SIZE / 2.;
+
+        // Past the last pipe of the course there's only the finish line.
+        let (ahead, target) = match run.track.next_gap(pos.x) {
+            Some(gap) => (gap.right - pos.x, gap.center_y()),
+            None => (DISTANCE_SCALE, run.track.bounds().h / 2.),
+        };
+
+        [
+            ahead / DISTANCE_SCALE,
+            (middle - target) / DISTANCE_SCALE,
+            run.speed().y / SPEED_SCALE,
+        ]
+    }
+
+    /// One hidden layer of tanh neurons feeding a single output.
+    fn think(&self, inputs: [f32; INPUTS]) -> f32 {
+        let (hidden_genes, output_genes) = self.genes.split_at((INPUTS + 1) * HIDDEN);
+
+        let hidden = hidden_genes.chunks(INPUTS + 1).map(|neuron| {
+            let sum: f32 = neuron.iter().zip(inputs).map(|(w, x)| w * x).sum();
+            (sum + neuron[INPUTS]).tanh()
+        });
+
+        let sum: f32 = output_genes.iter().zip(hidden).map(|(w, h)| w * h).sum();
+        sum + output_genes[HIDDEN]
+    }
+
+    /// Each gene comes from one parent or the other.
+    pub fn crossover(&self, other: &Brain, rng: &RandGenerator) -> Brain {
+        let genes = self.genes
+            .iter()
+            .zip(&other.genes)
+            .map(|(&a, &b)| if rng.gen_range(0, 2) == 0 { a } else { b })
+            .collect();
+
+        Brain { genes }
+    }
+
+    /// Nudges each gene by up to `strength`, with a chance of `rate`.
+    pub fn mutate(&mut self, rng: &RandGenerator, rate: f32, strength: f32) {
+        for gene in &mut self.genes {
+            if rng.gen_range(0., 1.) < rate {
+                *gene += rng.gen_range(-strength, strength);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn from_ron_round_trips() {
+        let brain = Brain {
+            genes: (0..GENES).map(|i| i as f32 / 10.).collect(),
+        };
+
+        let loaded = Brain::from_ron(&brain.to_ron()).unwrap();
+        assert_eq!(loaded.genes, brain.genes);
+    }
+
+    #[test]
+    fn from_ron_skips_the_wrong_gene_count() {
+        let brain = Brain { genes: vec![0.5; GENES - 1] };
+
+        assert!(Brain::from_ron(&brain.to_ron()).is_none());
+    }
+
+    #[test]
+    fn from_ron_skips_garbage() {
+        assert!(Brain::from_ron("not a genome").is_none());
+    }
+}
@@ -0,0 +1,198 @@
+use macroquad::file::load_string;
+use macroquad_platformer::{Tile, World};
+use nanoserde::DeJson;
+use tiled_info::MapInfo;
+
+use crate::track::Gap;
+
+const MAP: &str = "./assets/map.json";
+const OBSTACLES: &str = "obstacles";
+
+/// The hand-made course: which tiles are solid and where its gaps are.
+#[derive(Clone, Debug)]
+pub struct Course {
+    width: usize,
+    height: usize,
+    tile_width: f32,
+    tile_height: f32,
+    solid: Vec<bool>,
+    gaps: Vec<Gap>,
+}
+
+impl Course {
+    pub async fn load() -> Self {
+        let json = load_string(MAP)
+            .await
+            .unwrap();
+
+        Course::from_json(&json)
+    }
+
+    pub fn from_json(json: &str) -> Self {
+        let map: MapInfo = DeJson::deserialize_json(json).unwrap();
+        let layer = map.layer(OBSTACLES).unwrap();
+
+        let mut course = Course {
+            width: map.width,
+            height: map.height,
+            tile_width: map.tilewidth,
+            tile_height: map.tileheight,
+            solid: layer.data.iter().map(|&tile| tile != 0).collect(),
+            gaps: Vec::new(),
+        };
+
+        course.gaps = course.find_gaps();
+        course
+    }
+
+    pub fn add_obstacles(&self, world: &mut World) {
+        let obstacles = self.solid
+            .iter()
+            .map(|&solid| if solid { Tile::Solid } else { Tile::Empty })
+            .collect();
+
+        world.add_static_tiled_layer(
+            obstacles,
+            self.tile_width,
+            self.tile_height,
+            self.width,
+            1);
+    }
+
+    pub fn px_width(&self) -> f32 {
+        self.width as f32 * self.tile_width
+    }
+
+    pub fn px_height(&self) -> f32 {
+        self.height as f32 * self.tile_height
+    }
+
+    pub fn gaps(&self) -> &[Gap] {
+        &self.gaps
+    }
+
+    /// Pipes fully behind `x`.
+    pub fn passed(&self, x: f32) -> u32 {
+        self.gaps.iter().filter(|gap| gap.right <= x).count() as u32
+    }
+
+    fn is_solid(&self, x: usize, y: usize) -> bool {
+        self.solid[y * self.width + x]
+    }
+
+    /// A pipe is a run of columns with obstacle tiles in them, and its gap
+    /// the tallest stretch of rows that is clear in all of them.
+    fn find_gaps(&self) -> Vec<Gap> {
+        let filled: Vec<bool> = (0..self.width)
+            .map(|x| (0..self.height).any(|y| self.is_solid(x, y)))
+            .collect();
+
+        let mut gaps = Vec::new();
+        let mut x = 0;
+
+        while x < self.width {
+            if !filled[x] {
+                x += 1;
+                continue;
+            }
+
+            let left = x;
+            while x < self.width && filled[x] {
+                x += 1;
+            }
+
+            let (top, bottom) = self.tallest_clear(left..x);
+            gaps.push(Gap {
+                left: left as f32 * self.tile_width,
+                right: x as f32 * self.tile_width,
+                top: top as f32 * self.tile_height,
+                bottom: bottom as f32 * self.tile_height,
+            });
+        }
+
+        gaps
+    }
+
+    /// First and one past the last row of the tallest clear stretch.
+    fn tallest_clear(&self, columns: std::ops::Range<usize>) -> (usize, usize) {
+        let mut best = (0, 0);
+        let mut start = 0;
+
+        for y in 0..=self.height {
+            let clear = y < self.height && columns.clone().all(|x| !self.is_solid(x, y));
+
+            if clear {
+                continue;
+            }
+
+            if y - start > best.1 - best.0 {
+                best = (start, y);
+            }
+
+            start = y + 1;
+        }
+
+        best
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 6x4 tiles of 32px:
+    ///
+    /// ```text
+    /// .##.#.
+    /// ..#...
+    /// ......
+    /// .#....
+    /// ```
+    const MAP: &str = r#"{
+        "width": 6, "height": 4, "tilewidth": 32, "tileheight": 32,
+        "layers": [
+            { "name": "background", "data": [1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1] },
+            { "name": "obstacles", "data": [
+                0, 5, 5, 0, 5, 0,
+                0, 0, 5, 0, 0, 0,
+                0, 0, 0, 0, 0, 0,
+                0, 5, 0, 0, 0, 0
+            ] }
+        ]
+    }"#;
+
+    fn gap(left: f32, right: f32, top: f32, bottom: f32) -> Gap {
+        Gap { left, right, top, bottom }
+    }
+
+    #[test]
+    fn gaps_are_clear_in_every_column_of_a_pipe() {
+        let course = Course::from_json(MAP);
+
+        assert_eq!(course.gaps(), &[
+            gap(32., 96., 64., 96.),
+            gap(128., 160., 32., 128.),
+        ]);
+    }
+
+    #[test]
+    fn tallest_clear_picks_the_longest_stretch() {
+        let course = Course::from_json(MAP);
+
+        assert_eq!(course.tallest_clear(0..1), (0, 4));
+        assert_eq!(course.tallest_clear(1..2), (1, 3));
+        assert_eq!(course.tallest_clear(1..3), (2, 3));
+        assert_eq!(course.tallest_clear(4..5), (1, 4));
+    }
+
+    #[test]
+    fn passed_counts_pipes_fully_behind() {
+        let course = Course::from_json(MAP);
+
+        assert_eq!(course.passed(95.), 0);
+        assert_eq!(course.passed(96.), 1);
+        assert_eq!(course.passed(160.), 2);
+        assert_eq!(course.px_width(), 192.);
+        assert_eq!(course.px_height(), 128.);
+    }
+}
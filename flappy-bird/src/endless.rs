@@ -0,0 +1,205 @@
+use std::collections::VecDeque;
+
+use macroquad::{
+    math::{vec2, Rect, Vec2},
+    rand::RandGenerator,
+};
+use macroquad_platformer::{Solid, World};
+
+use crate::track::Gap;
+
+/// Same height as the Tiled course, so the bird feels the same.
+pub const HEIGHT: f32 = 256.;
+pub const PIPE_WIDTH: f32 = 32.;
+/// Shortest bit of pipe left above or below a gap.
+const MIN_PIPE: f32 = 24.;
+const FIRST_PIPE: f32 = 200.;
+/// How far behind and ahead of the bird pipes are kept. Enough to cover
+/// the view wherever the camera puts the bird in it.
+const BEHIND: f32 = HEIGHT / 2.;
+const AHEAD: f32 = HEIGHT + PIPE_WIDTH;
+
+/// Difficulty goes from the start values to the hardest ones over this
+/// many pipes, then stays there.
+const RAMP_PIPES: f32 = 40.;
+const START_GAP: f32 = 120.;
+const HARDEST_GAP: f32 = 80.;
+const START_SPACING: f32 = 170.;
+const HARDEST_SPACING: f32 = 110.;
+
+/// Pipe colours in `tiles.png`.
+const COLORS: u32 = 4;
+
+/// A column with a gap in it. Each half is a full-height solid sitting
+/// against the gap, so moving it is all it takes to reuse it.
+pub struct Pipe {
+    pub gap: Gap,
+    /// Which of the pipe colours in `tiles.png` to draw it in.
+    pub color: u32,
+    top: Solid,
+    bottom: Solid,
+}
+
+/// Pipes generated just ahead of the bird and dropped once they're behind
+/// it, forever. The same seed always gives the same pipes.
+pub struct Endless {
+    rng: RandGenerator,
+    pipes: VecDeque<Pipe>,
+    spare: Vec<(Solid, Solid)>,
+    generated: u32,
+    dropped: u32,
+}
+
+impl Endless {
+    pub fn new(seed: u64) -> Self {
+        let rng = RandGenerator::new();
+        rng.srand(seed);
+
+        Endless {
+            rng,
+            pipes: VecDeque::new(),
+            spare: Vec::new(),
+            generated: 0,
+            dropped: 0,
+        }
+    }
+
+    pub fn bounds(&self) -> Rect {
+        Rect::new(0., 0., f32::MAX / 2., HEIGHT)
+    }
+
+    /// Drops pipes behind the bird and adds new ones ahead of it.
+    pub fn update(&mut self, world: &mut World, bird_x: f32) {
+        while let Some(pipe) = self.pipes.front() {
+            if pipe.gap.right >= bird_x - BEHIND {
+                break;
+            }
+
+            let pipe = self.pipes.pop_front().unwrap();
+            self.spare.push((pipe.top, pipe.bottom));
+            self.dropped += 1;
+        }
+
+        loop {
+            let x = match self.pipes.back() {
+                Some(pipe) => pipe.gap.left + self.spacing(),
+                None => FIRST_PIPE,
+            };
+
+            if x > bird_x + AHEAD {
+                break;
+            }
+
+            self.add_pipe(world, x);
+        }
+    }
+
+    /// How far along the ramp from easy to hard the next pipe is, 0 to 1.
+    fn difficulty(&self) -> f32 {
+        (self.generated as f32 / RAMP_PIPES).min(1.)
+    }
+
+    fn spacing(&self) -> f32 {
+        lerp(START_SPACING, HARDEST_SPACING, self.difficulty())
+    }
+
+    fn add_pipe(&mut self, world: &mut World, x: f32) {
+        let gap = lerp(START_GAP, HARDEST_GAP, self.difficulty());
+        let gap_top = self.rng.gen_range(MIN_PIPE, HEIGHT - MIN_PIPE - gap);
+
+        let top_pos = vec2(x, gap_top - HEIGHT);
+        let bottom_pos = vec2(x, gap_top + gap);
+
+        let (top, bottom) = match self.spare.pop() {
+            Some((top, bottom)) => {
+                move_solid(world, top, top_pos);
+                move_solid(world, bottom, bottom_pos);
+                (top, bottom)
+            }
+            None => (
+                world.add_solid(top_pos, PIPE_WIDTH as i32, HEIGHT as i32),
+                world.add_solid(bottom_pos, PIPE_WIDTH as i32, HEIGHT as i32),
+            ),
+        };
+
+        self.pipes.push_back(Pipe {
+            gap: Gap {
+                left: x,
+                right: x + PIPE_WIDTH,
+                top: gap_top,
+                bottom: gap_top + gap,
+            },
+            color: self.rng.gen_range(0, COLORS),
+            top,
+            bottom,
+        });
+
+        self.generated += 1;
+    }
+
+    /// Pipes the bird's left edge, at `x`, is fully past.
+    pub fn passed(&self, x: f32) -> u32 {
+        let on_screen = self.pipes
+            .iter()
+            .take_while(|p| p.gap.right <= x)
+            .count();
+
+        self.dropped + on_screen as u32
+    }
+
+    /// Pipes around the bird, left to right.
+    pub fn pipes(&self) -> impl Iterator<Item = &Pipe> {
+        self.pipes.iter()
+    }
+}
+
+fn move_solid(world: &mut World, solid: Solid, to: Vec2) {
+    let delta = to - world.solid_pos(solid);
+    world.solid_move(solid, delta.x, delta.y);
+}
+
+fn lerp(from: f32, to: f32, t: f32) -> f32 {
+    from + (to - from) * t
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn passed_keeps_counting_dropped_pipes() {
+        let mut endless = Endless::new(7);
+        let mut world = World::new();
+        let mut seen = Vec::new();
+        let end = 3000.;
+
+        let mut x = 0.;
+        while x <= end {
+            endless.update(&mut world, x);
+
+            for pipe in endless.pipes() {
+                if !seen.contains(&pipe.gap) {
+                    seen.push(pipe.gap);
+                }
+            }
+
+            x += 8.;
+        }
+
+        let behind = seen.iter().filter(|gap| gap.right <= end).count();
+        assert!(endless.pipes().count() < seen.len(), "no pipes were dropped");
+        assert_eq!(endless.passed(end) as usize, behind);
+    }
+
+    #[test]
+    fn same_seed_gives_the_same_pipes() {
+        let (mut a, mut b) = (Endless::new(3), Endless::new(3));
+        let (mut world_a, mut world_b) = (World::new(), World::new());
+
+        a.update(&mut world_a, 1000.);
+        b.update(&mut world_b, 1000.);
+
+        let gaps = |e: &Endless| e.pipes().map(|p| p.gap).collect::<Vec<_>>();
+        assert_eq!(gaps(&a), gaps(&b));
+    }
+}
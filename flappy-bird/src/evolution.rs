@@ -0,0 +1,62 @@
+use macroquad::rand::RandGenerator;
+
+use crate::brain::Brain;
+
+/// Share of each generation carried over unchanged.
+const ELITE: f32 = 0.05;
+/// Birds picked at random for each parent, the fittest of them wins.
+const TOURNAMENT: usize = 3;
+const MUTATION_RATE: f32 = 0.1;
+const MUTATION_STRENGTH: f32 = 0.5;
+
+/// A generation of brains, and how the next one is bred from it.
+pub struct Population {
+    pub brains: Vec<Brain>,
+    pub generation: u32,
+    rng: RandGenerator,
+}
+
+impl Population {
+    pub fn new(size: usize, seed: u64) -> Self {
+        let rng = RandGenerator::new();
+        rng.srand(seed);
+
+        Population {
+            brains: (0..size).map(|_| Brain::random(&rng)).collect(),
+            generation: 0,
+            rng,
+        }
+    }
+
+    /// Replaces the brains with their children. `fitness` has a score for
+    /// every brain, in the same order.
+    pub fn evolve(&mut self, fitness: &[f32]) {
+        let mut ranked: Vec<usize> = (0..self.brains.len()).collect();
+        ranked.sort_by(|&a, &b| fitness[b].total_cmp(&fitness[a]));
+
+        let elite = ((self.brains.len() as f32 * ELITE) as usize).max(1);
+        let mut next: Vec<Brain> = ranked[..elite]
+            .iter()
+            .map(|&i| self.brains[i].clone())
+            .collect();
+
+        while next.len() < self.brains.len() {
+            let mother = &self.brains[self.pick(fitness)];
+            let father = &self.brains[self.pick(fitness)];
+
+            let mut child = mother.crossover(father, &self.rng);
+            child.mutate(&self.rng, MUTATION_RATE, MUTATION_STRENGTH);
+            next.push(child);
+        }
+
+        self.brains = next;
+        self.generation += 1;
+    }
+
+    fn pick(&self, fitness: &[f32]) -> usize {
+        (0..TOURNAMENT)
+            .map(|_| self.rng.gen_range(0, self.brains.len()))
+            .max_by(|&a, &b| fitness[a].total_cmp(&fitness[b]))
+            .unwrap()
+    }
+}
@@ -0,0 +1,11 @@
+//! Everything about flappy bird that doesn't need a window: the tracks,
+//! how the bird flies, replays, and the brains that learn to fly it. The
+//! game draws on top of it and the trainer runs it headless.
+pub mod brain;
+pub mod course;
+pub mod endless;
+pub mod evolution;
+pub mod physics;
+pub mod replay;
+pub mod run;
+pub mod track;
@@ -1,120 +1,267 @@
 mod map;
-use macroquad::{audio::{load_sound, play_sound, PlaySoundParams, Sound}, miniquad::native::linux_x11::libx11::Drawable, prelude::*};
-use macroquad_platformer::{Actor, Tile, World};
+mod pipes;
+use flappy_bird::{
+    brain::Brain,
+    course::Course,
+    physics::Physics,
+    replay::{self, Ghost, Replay},
+    run::{Run, State, PLAYER_SIZE, STEP},
+    track::{Mode, Track},
+};
+use fixed_clock::FixedClock;
+use follow_camera::FollowCamera;
+use macroquad::{audio::{load_sound, play_sound, PlaySoundParams, Sound}, miniquad::date, prelude::*};
 
-use crate::map::MapDrawer;
+use crate::{map::MapDrawer, pipes::PipeDrawer};
 
-struct Player {
-    actor: Actor,
-    texture: Texture2D,
-    speed: Vec2,
-}
+/// Wing positions in `bird.png`, up, middle and down, left to right.
+const BIRD_FRAMES: usize = 3;
+const MAX_FRAME_TIME: f32 = 0.25;
+const GHOST_COLOR: Color = Color::new(1., 1., 1., 0.4);
 
 pub struct Game {
     camera: Camera2D,
+    follow: FollowCamera,
+    mode: Mode,
+    run: Run,
+    /// The best run so far, flying along with this one.
+    ghost: Option<Ghost>,
+    recording: Replay,
+    best: Option<Replay>,
+    /// A flap waiting for the next step.
+    flap: bool,
+    /// The trained brain from `assets/genome.ron`, flying when `ai` is on.
+    pilot: Option<Brain>,
+    ai: bool,
+    course: Course,
     map: MapDrawer,
-    player: Player,
-    world: World,
-    sound: Sound
+    pipes: PipeDrawer,
+    texture: Texture2D,
+    sound: Sound,
+    physics: Physics,
 }
 
 impl Game {
-    async fn new() -> Game {
-        let map = MapDrawer::new().await;
-
-        let heigth = map.px_height();
-        let width = map.px_width();
-        let area = Rect::new(0., heigth, width / 7., -heigth);
-        let camera = Camera2D::from_display_rect(area);
-
-        let mut world = World::new();
-        let actor_texture = load_texture("./assets/player.png").await.unwrap();
-
-        let actor_pos = vec2(10., heigth / 2.);
-        let actor = world.add_actor(actor_pos, 32, 32);
-        let player = Player {
-            actor: actor,
-            texture: actor_texture,
-            speed: vec2(60., 0.),
-        };
+    async fn new(mode: Mode) -> Game {
+        let texture = load_texture("./assets/bird.png").await.unwrap();
+        let physics = Physics::load().await;
+        let course = Course::load().await;
+
+        let seed = Game::new_seed();
+        let run = Run::new(Track::new(mode, seed, &course), &physics);
 
         let sound = load_sound("./assets/jump.wav")
             .await
             .unwrap();
 
         let mut game =Game {
-            camera: camera,
-            map: map,
-            player: player,
-            world: world,
-            sound: sound
+            camera: Game::create_camera(&run.track),
+            follow: Game::create_follow(&run.track),
+            mode,
+            run,
+            ghost: None,
+            recording: Replay::new(mode, seed),
+            best: None,
+            flap: false,
+            pilot: Brain::load().await,
+            ai: false,
+            course,
+            map: MapDrawer::new().await,
+            pipes: PipeDrawer::new().await,
+            texture,
+            sound,
+            physics,
         };
 
-        game.load_obstacles();
+        game.follow.snap(game.run.pos());
+
+        game
+    }
+
+    /// Starts a new run in `mode`, racing the ghost of the best run on its
+    /// pipes. The best run is forgotten when the mode changes.
+    fn restart(&mut self, mode: Mode) {
+        if mode != self.mode {
+            self.best = None;
+        }
+
+        let seed = replay::seed_for(self.best.as_ref(), mode, Game::new_seed());
+        self.run = Run::new(Track::new(mode, seed, &self.course), &self.physics);
+        self.recording = Replay::new(mode, seed);
+        self.flap = false;
+
+        self.ghost = match self.best.clone() {
+            Some(best) => Some(Ghost::new(best, &self.course, &self.physics)),
+            None => None,
+        };
+
+        self.camera = Game::create_camera(&self.run.track);
+        self.follow = Game::create_follow(&self.run.track);
+        self.follow.snap(self.run.pos());
+        self.mode = mode;
+    }
+
+    fn new_seed() -> u64 {
+        (date::now() * 1000.) as u64
+    }
+
+    fn create_camera(track: &Track) -> Camera2D {
+        let view = track.view_size();
+        Camera2D::from_display_rect(Rect::new(0., view.y, view.x, -view.y))
+    }
 
-        return game
+    /// Keeps the bird in the left part of the screen, without showing
+    /// past the ends of the track.
+    fn create_follow(track: &Track) -> FollowCamera {
+        let view = track.view_size();
+        FollowCamera::new(track.bounds(), view)
+            .with_offset(vec2(view.x / 4., 0.))
     }
 
-    fn load_obstacles(&mut self) {
-        let tiles = self.map.get_tiles();
+    /// The part of the track the camera shows.
+    fn view(&self) -> Rect {
+        let size = self.run.track.view_size();
+        let corner = self.camera.target - size / 2.;
+        Rect::new(corner.x, corner.y, size.x, size.y)
+    }
 
-        let obstacles = tiles.map(|i| {
-            if i.2.is_some() {
-                return Tile::Solid;
-            }
+    fn is_playing(&self) -> bool {
+        self.run.is_playing()
+    }
+
+    fn best_score(&self) -> u32 {
+        self.best.as_ref().map_or(0, |best| best.score)
+    }
+
+    /// One fixed step for the bird and its ghost.
+    fn update(&mut self, dt: f32) {
+        if let Some(ghost) = &mut self.ghost {
+            ghost.step(&self.physics, dt);
+        }
+
+        if !self.is_playing() {
+            return;
+        }
+
+        let flap = match &self.pilot {
+            Some(pilot) if self.ai => pilot.wants_flap(&self.run),
+            _ => std::mem::take(&mut self.flap),
+        };
+
+        if flap {
+            self.recording.record(self.run.tick);
+            self.play_sound();
+        }
+
+        self.run.step(flap, &self.physics, dt);
+        self.follow.update(self.run.pos(), dt);
+
+        if !self.is_playing() {
+            self.end_run();
+        }
+    }
 
-            return Tile::Empty;
-        }).collect();
+    /// Keeps the recording if it beat the best run.
+    fn end_run(&mut self) {
+        self.recording.score = self.run.score;
 
-        self.world.add_static_tiled_layer(
-            obstacles, 
-            self.map.tile_width(), 
-            self.map.tile_height(), 
-            self.map.width() as usize,
-        1);
+        if self.best.is_none() || self.run.score > self.best_score() {
+            self.best = Some(self.recording.clone());
+        }
     }
 
-    fn draw(&mut self) {
+    fn draw(&mut self, alpha: f32) {
+        self.follow.apply(&mut self.camera, alpha);
         set_camera(&self.camera);
-        self.map.draw();
-        self.draw_player();
-        self.mov();
-        self.follow_player();
+        self.draw_track();
+
+        if let Some(ghost) = &self.ghost
+            && ghost.run.is_playing() {
+            self.draw_bird(&ghost.run, alpha, GHOST_COLOR);
+        }
+
+        self.draw_bird(&self.run, alpha, WHITE);
+
+        set_default_camera();
+        self.draw_score();
+
+        match self.run.state {
+            State::Playing => {}
+            State::GameOver => self.draw_end("GAME OVER"),
+            State::Finished => self.draw_end("YOU MADE IT!"),
+        }
+    }
+
+    fn draw_track(&self) {
+        match &self.run.track {
+            Track::Course(_) => self.map.draw(),
+            Track::Endless(endless) => self.pipes.draw(endless, self.view()),
+        }
+    }
+
+    fn draw_score(&self) {
+        let text = self.run.score.to_string();
+        let size = measure_text(&text, None, 60, 1.);
+        draw_text(&text, (screen_width() - size.width) / 2., 70., 60., WHITE);
+    }
+
+    fn draw_end(&self, title: &str) {
+        let center = screen_width() / 2.;
+        let middle = screen_height() / 2.;
+
+        draw_text(title, center - 120., middle - 20., 50., WHITE);
+        draw_text(&format!("{}   Score {}   Best {}", self.mode.name(), self.run.score, self.best_score()), center - 120., middle + 30., 30., WHITE);
+        draw_text("Press Enter to play again", center - 120., middle + 70., 25., WHITE);
+
+        let switch = format!("Press Tab for {} mode", self.mode.other().name().to_lowercase());
+        draw_text(&switch, center - 120., middle + 100., 25., WHITE);
+
+        if self.pilot.is_some() {
+            let pilot = if self.ai { "Press A to fly yourself" } else { "Press A to watch the AI fly" };
+            draw_text(pilot, center - 120., middle + 130., 25., WHITE);
+        }
     }
 
-    fn draw_player(&self) {
-        let pos = self.world.actor_pos(self.player.actor);
+    /// Tilted to follow the vertical speed: nose up after a flap, diving
+    /// as it falls.
+    fn draw_bird(&self, run: &Run, alpha: f32, color: Color) {
+        let pos = run.draw_pos(alpha);
+        let texture = &self.texture;
+
+        let width = texture.width() / BIRD_FRAMES as f32;
+        let frame = run.frame() as f32;
+
         draw_texture_ex(
-            &self.player.texture,
+            texture,
             pos.x,
             pos.y,
-            WHITE,
+            color,
             DrawTextureParams {
-                dest_size: Some(vec2(32., 32.)),
+                dest_size: Some(vec2(PLAYER_SIZE, PLAYER_SIZE)),
+                source: Some(Rect::new(frame * width, 0., width, texture.height())),
+                rotation: self.physics.tilt(run.speed().y),
                 ..Default::default()
             },
         );
     }
 
-    fn mov(&mut self) {
-        let actor = self.player.actor;
-
-        let dy = self.player.speed.y * get_frame_time();
-        let dx = self.player.speed.x * get_frame_time();
-        self.world.move_v(actor, dy);
-        self.world.move_h(actor, dx);
+    /// The flap itself waits for the next step, so it lands on a tick.
+    fn jump(&mut self) {
+        if !self.is_playing() || self.ai {
+            return;
+        }
 
-        self.player.speed.y += 200. * get_frame_time();
+        self.flap = true;
     }
 
-    fn jump(&mut self) {
-        self.player.speed.y = -120.;
-        self.play_sound();
-    }
+    /// Hands the bird to the trained brain or back, starting over.
+    fn toggle_ai(&mut self) {
+        if self.pilot.is_none() {
+            return;
+        }
 
-    fn follow_player(&mut self) {
-        let pos = self.world.actor_pos(self.player.actor);
-        self.camera.target = vec2(pos.x, pos.y);
+        self.ai = !self.ai;
+        self.restart(self.mode);
     }
 
     fn play_sound(&self) {
@@ -126,7 +273,8 @@ impl Game {
 }
 #[macroquad::main("Flappy")]
 async fn main() {
-    let mut game = Game::new().await;
+    let mut game = Game::new(Mode::Course).await;
+    let mut clock = FixedClock::new(STEP, MAX_FRAME_TIME);
 
     loop {
         clear_background(GREEN);
@@ -135,7 +283,23 @@ async fn main() {
             game.jump();
         }
 
-        game.draw();
+        if !game.is_playing() && is_key_pressed(KeyCode::Enter) {
+            game.restart(game.mode);
+        }
+
+        if !game.is_playing() && is_key_pressed(KeyCode::Tab) {
+            game.restart(game.mode.other());
+        }
+
+        if !game.is_playing() && is_key_pressed(KeyCode::A) {
+            game.toggle_ai();
+        }
+
+        for _ in 0..clock.advance(get_frame_time()) {
+            game.update(clock.step());
+        }
+
+        game.draw(clock.alpha());
 
         next_frame().await
     }
@@ -1,8 +1,9 @@
 use macroquad::{
-    color::WHITE, file::load_string, math::{vec2, Rect}, texture::{draw_texture, draw_texture_ex, load_texture, DrawTextureParams, Texture2D}
+    color::WHITE, file::load_string, math::{vec2, Rect}, texture::{draw_texture_ex, load_texture, DrawTextureParams, Texture2D}
 };
-use macroquad_tiled::{self as tiled, Map, TilesIterator};
+use macroquad_tiled::{self as tiled, Map};
 
+/// Draws the course. What the bird can hit is in `flappy_bird::course`.
 pub struct MapDrawer {
     map: Map,
     background: Texture2D,
@@ -21,19 +22,19 @@ impl MapDrawer {
         let texture_tile = load_texture("./assets/tiles.png")
             .await
             .unwrap();
-        
+
         let texture_param = ("tiles.png", texture_tile);
 
         let map = tiled::load_map(
-            &json_map, 
-            &[texture_param], 
+            &json_map,
+            &[texture_param],
             &[])
             .unwrap();
 
 
-        MapDrawer { 
-            map: map,
-            background: background 
+        MapDrawer {
+            map,
+            background,
         }
     }
 
@@ -58,32 +59,16 @@ impl MapDrawer {
 
 
     pub fn px_width(&self) -> f32 {
-        let width = self.map.raw_tiled_map.width * 
+        let width = self.map.raw_tiled_map.width *
             self.map.raw_tiled_map.tilewidth;
 
-        return width as f32;
+        width as f32
     }
 
     pub fn px_height(&self) -> f32 {
-        let height = self.map.raw_tiled_map.height * 
+        let height = self.map.raw_tiled_map.height *
             self.map.raw_tiled_map.tileheight;
 
-        return height as f32;
-    }
-
-    pub fn get_tiles(&self) -> TilesIterator {
-        self.map.tiles("obstacles", None)
-    }
-
-    pub fn tile_width(&self) -> f32 {
-        return self.map.raw_tiled_map.tilewidth as f32;
-    }
-
-    pub fn tile_height(&self) -> f32 {
-        return self.map.raw_tiled_map.tileheight as f32;
-    }
-
-    pub fn width(&self) -> f32 {
-        return self.map.raw_tiled_map.width as f32;
+        height as f32
     }
 }
@@ -0,0 +1,45 @@
+use macroquad::file::load_string;
+use nanoserde::DeRon;
+
+const CONFIG: &str = "./assets/physics.ron";
+
+/// How the bird moves and looks while moving, loaded from
+/// `assets/physics.ron` so it can be tuned without rebuilding.
+#[derive(DeRon, Clone, Debug)]
+pub struct Physics {
+    /// Forward speed, px/s.
+    pub speed: f32,
+    /// Vertical speed a flap sets, px/s. Negative is up.
+    pub flap: f32,
+    /// px/s².
+    pub gravity: f32,
+    /// Degrees of tilt per px/s of vertical speed.
+    pub tilt_per_speed: f32,
+    /// Limits of the tilt in degrees, nose up is negative.
+    pub max_tilt_up: f32,
+    pub max_tilt_down: f32,
+    /// Seconds each wing frame of the sprite sheet is shown.
+    pub flap_frame_time: f32,
+}
+
+impl Physics {
+    pub async fn load() -> Self {
+        let config = load_string(CONFIG)
+            .await
+            .unwrap();
+
+        Physics::from_ron(&config)
+    }
+
+    pub fn from_ron(config: &str) -> Self {
+        DeRon::deserialize_ron(config)
+            .unwrap()
+    }
+
+    /// Tilt in radians for a vertical speed.
+    pub fn tilt(&self, speed_y: f32) -> f32 {
+        (speed_y * self.tilt_per_speed)
+            .clamp(self.max_tilt_up, self.max_tilt_down)
+            .to_radians()
+    }
+}
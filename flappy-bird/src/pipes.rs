@@ -0,0 +1,83 @@
+use macroquad::{
+    color::WHITE,
+    math::{vec2, Rect, Vec2},
+    texture::{draw_texture, draw_texture_ex, load_texture, DrawTextureParams, Texture2D},
+};
+use flappy_bird::endless::{Endless, Pipe, HEIGHT, PIPE_WIDTH};
+
+const CAP_HEIGHT: f32 = 16.;
+
+/// Draws endless pipes from the caps and body in `tiles.png`, over a
+/// tiled background.
+pub struct PipeDrawer {
+    tiles: Texture2D,
+    background: Texture2D,
+}
+
+impl PipeDrawer {
+    pub async fn new() -> Self {
+        let tiles = load_texture("./assets/tiles.png")
+            .await
+            .unwrap();
+
+        let background = load_texture("./assets/background.png")
+            .await
+            .unwrap();
+
+        PipeDrawer {
+            tiles,
+            background,
+        }
+    }
+
+    pub fn draw(&self, endless: &Endless, view: Rect) {
+        let width = self.background.width();
+        let mut x = (view.x / width).floor() * width;
+
+        while x < view.right() {
+            draw_texture(&self.background, x, 0., WHITE);
+            x += width;
+        }
+
+        for pipe in endless.pipes() {
+            self.draw_pipe(pipe);
+        }
+    }
+
+    /// The top half ends in the pipe's bottom cap and the bottom half
+    /// starts with its top cap, both stretched from the same column of
+    /// the tileset.
+    fn draw_pipe(&self, pipe: &Pipe) {
+        let column = pipe.color as f32 * PIPE_WIDTH;
+        let body = Rect::new(column, CAP_HEIGHT, PIPE_WIDTH, PIPE_WIDTH + CAP_HEIGHT);
+        let top_cap = Rect::new(column, 0., PIPE_WIDTH, CAP_HEIGHT);
+        let bottom_cap = Rect::new(column, PIPE_WIDTH * 2., PIPE_WIDTH, CAP_HEIGHT);
+
+        let x = pipe.gap.left;
+        let cap_y = pipe.gap.top - CAP_HEIGHT;
+        self.draw_part(body, vec2(x, 0.), cap_y);
+        self.draw_part(bottom_cap, vec2(x, cap_y), CAP_HEIGHT);
+
+        let gap_bottom = pipe.gap.bottom;
+        self.draw_part(top_cap, vec2(x, gap_bottom), CAP_HEIGHT);
+        self.draw_part(body, vec2(x, gap_bottom + CAP_HEIGHT), HEIGHT - gap_bottom - CAP_HEIGHT);
+    }
+
+    fn draw_part(&self, source: Rect, pos: Vec2, height: f32) {
+        if height <= 0. {
+            return;
+        }
+
+        draw_texture_ex(
+            &self.tiles,
+            pos.x,
+            pos.y,
+            WHITE,
+            DrawTextureParams {
+                dest_size: Some(vec2(PIPE_WIDTH, height)),
+                source: Some(source),
+                ..Default::default()
+            },
+        );
+    }
+}
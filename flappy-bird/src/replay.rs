@@ -0,0 +1,164 @@
+use crate::{course::Course, physics::Physics, run::Run, track::{Mode, Track}};
+
+/// Everything needed to fly a run again: the track it was on and the
+/// ticks the bird flapped on. Ticks without a flap aren't stored.
+#[derive(Clone, Debug)]
+pub struct Replay {
+    pub mode: Mode,
+    pub seed: u64,
+    pub flaps: Vec<u32>,
+    pub score: u32,
+}
+
+impl Replay {
+    pub fn new(mode: Mode, seed: u64) -> Self {
+        Replay {
+            mode,
+            seed,
+            flaps: Vec::new(),
+            score: 0,
+        }
+    }
+
+    pub fn record(&mut self, tick: u32) {
+        self.flaps.push(tick);
+    }
+}
+
+/// The seed for a new run in `mode` racing `best`: the best run's own, so
+/// its ghost flies through the same pipes, or `fresh` when there's nothing
+/// to race in that mode.
+pub fn seed_for(best: Option<&Replay>, mode: Mode, fresh: u64) -> u64 {
+    match best {
+        Some(best) if best.mode == mode => best.seed,
+        _ => fresh,
+    }
+}
+
+/// A recorded run flown again next to the live one.
+pub struct Ghost {
+    pub run: Run,
+    replay: Replay,
+    next_flap: usize,
+}
+
+impl Ghost {
+    pub fn new(replay: Replay, course: &Course, physics: &Physics) -> Self {
+        let track = Track::new(replay.mode, replay.seed, course);
+
+        Ghost {
+            run: Run::new(track, physics),
+            replay,
+            next_flap: 0,
+        }
+    }
+
+    pub fn step(&mut self, physics: &Physics, dt: f32) {
+        let flap = self.replay.flaps.get(self.next_flap) == Some(&self.run.tick);
+
+        if flap {
+            self.next_flap += 1;
+        }
+
+        self.run.step(flap, physics, dt);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{run::STEP, track::Gap};
+
+    const MAX_TICKS: u32 = 120 * 60;
+
+    fn physics() -> Physics {
+        Physics::from_ron(include_str!("../assets/physics.ron"))
+    }
+
+    fn course() -> Course {
+        Course::from_json(include_str!("../assets/map.json"))
+    }
+
+    /// Flies a run that flaps whenever it sinks below the middle, and
+    /// records it. Also returns how many steps it took, counting the one
+    /// it crashed on.
+    fn record(mode: Mode, seed: u64, course: &Course, physics: &Physics) -> (Run, Replay, u32) {
+        let mut run = Run::new(Track::new(mode, seed, course), physics);
+        let mut replay = Replay::new(mode, seed);
+        let middle = run.track.bounds().h / 2.;
+        let mut steps = 0;
+
+        while run.is_playing() && run.tick < MAX_TICKS {
+            let flap = run.pos().y > middle && run.speed().y > 0.;
+
+            if flap {
+                replay.record(run.tick);
+            }
+
+            run.step(flap, physics, STEP);
+            steps += 1;
+        }
+
+        replay.score = run.score;
+        (run, replay, steps)
+    }
+
+    fn assert_replays(mode: Mode, seed: u64) {
+        let (physics, course) = (physics(), course());
+        let (run, replay, steps) = record(mode, seed, &course, &physics);
+        assert!(!replay.flaps.is_empty());
+
+        let mut ghost = Ghost::new(replay, &course, &physics);
+
+        for _ in 0..steps {
+            ghost.step(&physics, STEP);
+        }
+
+        assert_eq!(ghost.run.tick, run.tick);
+        assert_eq!(ghost.run.score, run.score);
+        assert_eq!(ghost.run.state, run.state);
+        assert_eq!(ghost.run.pos(), run.pos());
+    }
+
+    fn endless_gaps(track: &Track) -> Vec<Gap> {
+        match track {
+            Track::Endless(endless) => endless.pipes().map(|pipe| pipe.gap).collect(),
+            Track::Course(_) => panic!("not an endless track"),
+        }
+    }
+
+    #[test]
+    fn restarted_endless_run_shares_the_ghosts_track() {
+        let (physics, course) = (physics(), course());
+        let (_, best, steps) = record(Mode::Endless, 42, &course, &physics);
+
+        let seed = seed_for(Some(&best), Mode::Endless, 7);
+        let (run, _, _) = record(Mode::Endless, seed, &course, &physics);
+        let mut ghost = Ghost::new(best, &course, &physics);
+
+        for _ in 0..steps {
+            ghost.step(&physics, STEP);
+        }
+
+        assert!(!endless_gaps(&run.track).is_empty());
+        assert_eq!(endless_gaps(&run.track), endless_gaps(&ghost.run.track));
+    }
+
+    #[test]
+    fn seed_for_is_fresh_without_a_best_run_in_the_mode() {
+        let best = Replay::new(Mode::Endless, 42);
+
+        assert_eq!(seed_for(None, Mode::Endless, 7), 7);
+        assert_eq!(seed_for(Some(&best), Mode::Course, 7), 7);
+    }
+
+    #[test]
+    fn course_replays_the_same() {
+        assert_replays(Mode::Course, 0);
+    }
+
+    #[test]
+    fn endless_replays_the_same() {
+        assert_replays(Mode::Endless, 42);
+    }
+}
@@ -0,0 +1,140 @@
+use macroquad::math::{vec2, Vec2};
+use macroquad_platformer::{Actor, World};
+
+use crate::{physics::Physics, track::Track};
+
+pub const PLAYER_SIZE: f32 = 32.;
+/// Length of a step in seconds. Replays and trained brains only fly the
+/// same when they're stepped at this rate.
+pub const STEP: f32 = 1. / 120.;
+/// Order the wing frames of `bird.png` play in.
+pub const FLAP_CYCLE: [usize; 4] = [0, 1, 2, 1];
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum State {
+    Playing,
+    GameOver,
+    /// Made it to the end of the course.
+    Finished,
+}
+
+/// One bird flying one track in its own world. It only moves in fixed
+/// steps, so the same seed and the same flaps on the same ticks always
+/// give the same run.
+pub struct Run {
+    world: World,
+    pub track: Track,
+    actor: Actor,
+    speed: Vec2,
+    prev_pos: Vec2,
+    pub state: State,
+    /// Steps taken so far.
+    pub tick: u32,
+    pub score: u32,
+    /// Position in `FLAP_CYCLE` and how long it has been shown.
+    frame: usize,
+    frame_time: f32,
+}
+
+impl Run {
+    pub fn new(mut track: Track, physics: &Physics) -> Run {
+        let mut world = World::new();
+
+        let start = vec2(10., track.bounds().h / 2.);
+        track.build(&mut world, start.x);
+        let actor = world.add_actor(start, PLAYER_SIZE as i32, PLAYER_SIZE as i32);
+
+        Run {
+            world,
+            track,
+            actor,
+            speed: vec2(physics.speed, 0.),
+            prev_pos: start,
+            state: State::Playing,
+            tick: 0,
+            score: 0,
+            frame: 0,
+            frame_time: 0.,
+        }
+    }
+
+    pub fn is_playing(&self) -> bool {
+        self.state == State::Playing
+    }
+
+    pub fn pos(&self) -> Vec2 {
+        self.world.actor_pos(self.actor)
+    }
+
+    /// Position `alpha` of the way between the last two steps.
+    pub fn draw_pos(&self, alpha: f32) -> Vec2 {
+        self.prev_pos.lerp(self.pos(), alpha)
+    }
+
+    pub fn speed(&self) -> Vec2 {
+        self.speed
+    }
+
+    pub fn frame(&self) -> usize {
+        FLAP_CYCLE[self.frame]
+    }
+
+    /// Advances one fixed step of `dt` seconds, flapping first if asked.
+    pub fn step(&mut self, flap: bool, physics: &Physics, dt: f32) {
+        if !self.is_playing() {
+            return;
+        }
+
+        self.prev_pos = self.pos();
+
+        if flap {
+            self.speed.y = physics.flap;
+        }
+
+        if !self.mov(physics, dt) || self.is_out_of_map() {
+            self.state = State::GameOver;
+            return;
+        }
+
+        self.animate(physics, dt);
+        self.tick += 1;
+
+        let pos = self.pos();
+        self.score = self.track.passed(pos.x);
+
+        if let Some(finish) = self.track.finish()
+            && pos.x + PLAYER_SIZE >= finish {
+            self.state = State::Finished;
+        }
+
+        self.track.update(&mut self.world, pos.x);
+    }
+
+    /// Returns false when the bird hit an obstacle.
+    fn mov(&mut self, physics: &Physics, dt: f32) -> bool {
+        let dy = self.speed.y * dt;
+        let dx = self.speed.x * dt;
+        let moved_v = self.world.move_v(self.actor, dy);
+        let moved_h = self.world.move_h(self.actor, dx);
+
+        self.speed.y += physics.gravity * dt;
+
+        moved_v && moved_h
+    }
+
+    /// Gone off the top or the bottom of the screen.
+    fn is_out_of_map(&self) -> bool {
+        let pos = self.pos();
+        pos.y + PLAYER_SIZE < 0. || pos.y > self.track.bounds().h
+    }
+
+    /// Steps through the wing frames.
+    fn animate(&mut self, physics: &Physics, dt: f32) {
+        self.frame_time += dt;
+
+        if self.frame_time >= physics.flap_frame_time {
+            self.frame_time = 0.;
+            self.frame = (self.frame + 1) % FLAP_CYCLE.len();
+        }
+    }
+}
@@ -0,0 +1,116 @@
+use macroquad::math::{vec2, Rect, Vec2};
+use macroquad_platformer::World;
+
+use crate::{
+    course::Course,
+    endless::{self, Endless},
+};
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Mode {
+    /// The hand-made Tiled map, the same every run.
+    Course,
+    /// Random pipes that never end.
+    Endless,
+}
+
+impl Mode {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Mode::Course => "COURSE",
+            Mode::Endless => "ENDLESS",
+        }
+    }
+
+    pub fn other(&self) -> Mode {
+        match self {
+            Mode::Course => Mode::Endless,
+            Mode::Endless => Mode::Course,
+        }
+    }
+}
+
+/// The opening through a pipe, in pixels.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Gap {
+    pub left: f32,
+    pub right: f32,
+    pub top: f32,
+    pub bottom: f32,
+}
+
+impl Gap {
+    pub fn center_y(&self) -> f32 {
+        (self.top + self.bottom) / 2.
+    }
+}
+
+/// Where the pipes come from.
+pub enum Track {
+    Course(Course),
+    Endless(Endless),
+}
+
+impl Track {
+    /// The seed only matters for endless pipes.
+    pub fn new(mode: Mode, seed: u64, course: &Course) -> Track {
+        match mode {
+            Mode::Course => Track::Course(course.clone()),
+            Mode::Endless => Track::Endless(Endless::new(seed)),
+        }
+    }
+
+    /// Adds the obstacles around the bird at `x` to a fresh `world`.
+    pub fn build(&mut self, world: &mut World, x: f32) {
+        match self {
+            Track::Course(course) => course.add_obstacles(world),
+            Track::Endless(endless) => endless.update(world, x),
+        }
+    }
+
+    pub fn bounds(&self) -> Rect {
+        match self {
+            Track::Course(course) => Rect::new(0., 0., course.px_width(), course.px_height()),
+            Track::Endless(endless) => endless.bounds(),
+        }
+    }
+
+    /// Size of the part of the track on screen.
+    pub fn view_size(&self) -> Vec2 {
+        match self {
+            Track::Course(course) => vec2(course.px_width() / 7., course.px_height()),
+            Track::Endless(_) => vec2(endless::HEIGHT, endless::HEIGHT),
+        }
+    }
+
+    /// Where the run is won, if it can be.
+    pub fn finish(&self) -> Option<f32> {
+        match self {
+            Track::Course(course) => Some(course.px_width()),
+            Track::Endless(_) => None,
+        }
+    }
+
+    /// Keeps the pipes around the bird at `x` in place.
+    pub fn update(&mut self, world: &mut World, x: f32) {
+        if let Track::Endless(endless) = self {
+            endless.update(world, x);
+        }
+    }
+
+    /// Pipes fully behind `x`.
+    pub fn passed(&self, x: f32) -> u32 {
+        match self {
+            Track::Course(course) => course.passed(x),
+            Track::Endless(endless) => endless.passed(x),
+        }
+    }
+
+    /// The first gap whose pipe isn't fully behind `x` yet.
+    pub fn next_gap(&self, x: f32) -> Option<Gap> {
+        match self {
+            Track::Course(course) => course.gaps().iter().find(|gap| gap.right > x).copied(),
+            Track::Endless(endless) => endless.pipes().map(|pipe| pipe.gap).find(|gap| gap.right > x),
+        }
+    }
+}
@@ -0,0 +1,229 @@
+use macroquad::{
+    camera::Camera2D,
+    math::{Rect, Vec2, vec2},
+};
+
+/// Moves a 2D camera after a point, the way side-scrollers do.
+///
+/// The point can move inside a dead-zone without moving the camera, the
+/// camera eases towards it instead of snapping, and the view never shows
+/// anything outside `bounds`.
+pub struct FollowCamera {
+    bounds: Rect,
+    view: Vec2,
+    dead_zone: Vec2,
+    offset: Vec2,
+    smoothing: f32,
+    no_backtracking: bool,
+    goal: Vec2,
+    target: Vec2,
+    prev_target: Vec2,
+    min_x: f32,
+}
+
+impl FollowCamera {
+    /// `view` is the size of what the camera shows, in world units.
+    pub fn new(bounds: Rect, view: Vec2) -> Self {
+        let center = bounds.center();
+
+        FollowCamera {
+            bounds,
+            view,
+            dead_zone: vec2(0., 0.),
+            offset: vec2(0., 0.),
+            smoothing: 0.,
+            no_backtracking: false,
+            goal: center,
+            target: center,
+            prev_target: center,
+            min_x: bounds.x,
+        }
+    }
+
+    /// How far, each way from the center, the point moves freely.
+    pub fn with_dead_zone(mut self, dead_zone: Vec2) -> Self {
+        self.dead_zone = dead_zone;
+        self
+    }
+
+    /// Where the point sits from the center of the view. A positive x
+    /// shows more of what is ahead.
+    pub fn with_offset(mut self, offset: Vec2) -> Self {
+        self.offset = offset;
+        self
+    }
+
+    /// How quickly the camera catches up, per second. Zero snaps.
+    pub fn with_smoothing(mut self, smoothing: f32) -> Self {
+        self.smoothing = smoothing;
+        self
+    }
+
+    /// Never scroll back left, like the original Super Mario Bros.
+    pub fn without_backtracking(mut self) -> Self {
+        self.no_backtracking = true;
+        self
+    }
+
+    /// Jumps straight to `focus`, forgetting how far it had scrolled.
+    pub fn snap(&mut self, focus: Vec2) {
+        self.min_x = self.bounds.x;
+        self.goal = self.clamp(focus + self.offset);
+        self.target = self.goal;
+        self.prev_target = self.goal;
+    }
+
+    pub fn update(&mut self, focus: Vec2, dt: f32) {
+        let focus = focus + self.offset;
+        let mut goal = self.goal;
+
+        goal.x = follow_axis(goal.x, focus.x, self.dead_zone.x);
+        goal.y = follow_axis(goal.y, focus.y, self.dead_zone.y);
+
+        if self.no_backtracking {
+            goal.x = goal.x.max(self.min_x);
+        }
+
+        self.goal = self.clamp(goal);
+        self.prev_target = self.target;
+
+        if self.smoothing <= 0. {
+            self.target = self.goal;
+        } else {
+            let t = 1. - (-self.smoothing * dt).exp();
+            self.target = self.target.lerp(self.goal, t);
+        }
+
+        if self.no_backtracking {
+            self.target.x = self.target.x.max(self.prev_target.x);
+            self.min_x = self.target.x;
+        }
+    }
+
+    /// The center of the view, `alpha` of the way from the previous update
+    /// to the last one.
+    pub fn target(&self, alpha: f32) -> Vec2 {
+        self.prev_target.lerp(self.target, alpha)
+    }
+
+    pub fn apply(&self, camera: &mut Camera2D, alpha: f32) {
+        camera.target = self.target(alpha);
+    }
+
+    /// Keeps the whole view inside the bounds, or centers it when the
+    /// bounds are smaller than the view.
+    fn clamp(&self, center: Vec2) -> Vec2 {
+        vec2(
+            clamp_axis(center.x, self.bounds.x, self.bounds.w, self.view.x),
+            clamp_axis(center.y, self.bounds.y, self.bounds.h, self.view.y),
+        )
+    }
+}
+
+/// Moves `goal` just enough to keep `focus` within `dead_zone` of it.
+fn follow_axis(goal: f32, focus: f32, dead_zone: f32) -> f32 {
+    if focus > goal + dead_zone {
+        return focus - dead_zone;
+    }
+
+    if focus < goal - dead_zone {
+        return focus + dead_zone;
+    }
+
+    goal
+}
+
+fn clamp_axis(center: f32, start: f32, size: f32, view: f32) -> f32 {
+    if view >= size {
+        return start + size / 2.;
+    }
+
+    center.clamp(start + view / 2., start + size - view / 2.)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const STEP: f32 = 1. / 120.;
+
+    /// A 1000x200 map seen through a 100x100 view.
+    fn camera() -> FollowCamera {
+        FollowCamera::new(Rect::new(0., 0., 1000., 200.), vec2(100., 100.))
+    }
+
+    #[test]
+    fn centers_on_a_map_narrower_than_the_view() {
+        let mut camera = FollowCamera::new(Rect::new(0., 0., 80., 60.), vec2(100., 100.));
+
+        camera.snap(vec2(70., 50.));
+        assert_eq!(camera.target(1.), vec2(40., 30.));
+
+        camera.update(vec2(5., 5.), STEP);
+        assert_eq!(camera.target(1.), vec2(40., 30.));
+    }
+
+    #[test]
+    fn clamps_to_the_edges_of_the_map() {
+        let mut camera = camera();
+
+        camera.snap(vec2(0., 0.));
+        assert_eq!(camera.target(1.), vec2(50., 50.));
+
+        camera.snap(vec2(2000., 500.));
+        assert_eq!(camera.target(1.), vec2(950., 150.));
+    }
+
+    #[test]
+    fn stays_still_inside_the_dead_zone() {
+        let mut camera = camera().with_dead_zone(vec2(20., 20.));
+        camera.snap(vec2(300., 100.));
+
+        for x in [310., 320., 285., 280.] {
+            camera.update(vec2(x, 110.), STEP);
+            assert_eq!(camera.target(1.), vec2(300., 100.));
+        }
+
+        camera.update(vec2(330., 100.), STEP);
+        assert_eq!(camera.target(1.), vec2(310., 100.));
+    }
+
+    #[test]
+    fn never_scrolls_back_without_backtracking() {
+        let mut camera = camera()
+            .with_smoothing(8.)
+            .without_backtracking();
+        camera.snap(vec2(100., 100.));
+
+        let mut last = camera.target(1.).x;
+
+        for x in [200., 400., 150., 50., 300., 0.] {
+            for _ in 0..60 {
+                camera.update(vec2(x, 100.), STEP);
+
+                let target = camera.target(1.).x;
+                assert!(target >= last, "moved left from {last} to {target}");
+                last = target;
+            }
+        }
+
+        assert!(last > 300.);
+    }
+
+    #[test]
+    fn snap_jumps_straight_there() {
+        let mut camera = camera()
+            .with_offset(vec2(20., 0.))
+            .with_smoothing(8.)
+            .without_backtracking();
+
+        camera.snap(vec2(600., 100.));
+        assert_eq!(camera.target(0.), vec2(620., 100.));
+        assert_eq!(camera.target(1.), vec2(620., 100.));
+
+        // Even without backtracking, a snap can go back left.
+        camera.snap(vec2(200., 100.));
+        assert_eq!(camera.target(0.), vec2(220., 100.));
+        assert_eq!(camera.target(1.), vec2(220., 100.));
+    }
+}
@@ -0,0 +1,123 @@
+use std::{collections::HashMap, hash::Hash};
+
+use macroquad::{
+    color::WHITE,
+    math::{Rect, Vec2},
+    texture::{DrawTextureParams, Texture2D, draw_texture_ex},
+};
+
+/// Frames of a sprite sheet played one after the other.
+pub struct Animation {
+    frames: Vec<Rect>,
+    frame_time: f32,
+    looped: bool,
+}
+
+impl Animation {
+    pub fn new(frames: Vec<Rect>, frame_time: f32, looped: bool) -> Self {
+        Animation {
+            frames,
+            frame_time,
+            looped,
+        }
+    }
+
+    /// `count` frames of `size` in a row, starting at `start` and `step`
+    /// pixels apart.
+    pub fn strip(start: Vec2, size: Vec2, step: f32, count: usize, frame_time: f32) -> Self {
+        let frames = (0..count)
+            .map(|i| Rect::new(start.x + i as f32 * step, start.y, size.x, size.y))
+            .collect();
+
+        Animation::new(frames, frame_time, true)
+    }
+
+    /// A single frame that never changes.
+    pub fn still(frame: Rect) -> Self {
+        Animation::new(vec![frame], 0., false)
+    }
+
+    pub fn frame(&self, time: f32) -> Rect {
+        if self.frames.len() == 1 || self.frame_time <= 0. {
+            return self.frames[0];
+        }
+
+        let i = (time / self.frame_time) as usize;
+
+        if self.looped {
+            return self.frames[i % self.frames.len()];
+        }
+
+        self.frames[i.min(self.frames.len() - 1)]
+    }
+}
+
+/// The animations of a sprite, one per state, sharing a texture.
+pub struct AnimationSet<S> {
+    texture: Texture2D,
+    animations: HashMap<S, Animation>,
+    state: S,
+    time: f32,
+    pub flip_x: bool,
+    pub flip_y: bool,
+}
+
+impl<S: Copy + Eq + Hash> AnimationSet<S> {
+    pub fn new(texture: Texture2D, state: S) -> Self {
+        AnimationSet {
+            texture,
+            animations: HashMap::new(),
+            state,
+            time: 0.,
+            flip_x: false,
+            flip_y: false,
+        }
+    }
+
+    pub fn with(mut self, state: S, animation: Animation) -> Self {
+        self.animations.insert(state, animation);
+        self
+    }
+
+    pub fn state(&self) -> S {
+        self.state
+    }
+
+    /// Switches animation, starting the new one from its first frame.
+    pub fn set_state(&mut self, state: S) {
+        if self.state != state {
+            self.state = state;
+            self.time = 0.;
+        }
+    }
+
+    pub fn update(&mut self, dt: f32) {
+        self.time += dt;
+    }
+
+    /// The frame showing right now.
+    pub fn frame(&self) -> Option<Rect> {
+        self.animations
+            .get(&self.state)
+            .map(|animation| animation.frame(self.time))
+    }
+
+    pub fn draw(&self, pos: Vec2) {
+        let Some(animation) = self.animations.get(&self.state) else {
+            return;
+        };
+
+        draw_texture_ex(
+            &self.texture,
+            pos.x,
+            pos.y,
+            WHITE,
+            DrawTextureParams {
+                source: Some(animation.frame(self.time)),
+                flip_x: self.flip_x,
+                flip_y: self.flip_y,
+                ..Default::default()
+            },
+        );
+    }
+}
@@ -1,8 +1,39 @@
+use macroquad::math::{vec2, Vec2};
+
 pub const PLAYER_SIZE: f32 = 16.;
 pub const TILE_GROUND: f32 = 13.;
 pub const TILE_SIZE: f32 = 16.;
 pub const PLAYER_SPEED: f32 = 90.;
 pub const FRAME_EVERY: f32 = 0.1;
+pub const ENEMY_FRAME_EVERY: f32 = 0.2;
+pub const SQUASHED_TIME: f32 = 0.5;
 pub const JUMP: f32 = 180.;
 pub const GRAVITY: f32 = 250.;
 pub const SPEED_ENEMY: f32 = 40.;
+pub const LIVES: u32 = 3;
+pub const RESPAWN_DELAY: f32 = 1.5;
+pub const LEVEL_CLEAR_DELAY: f32 = 3.;
+pub const MUSHROOM_SPEED: f32 = 50.;
+pub const INVINCIBLE_TIME: f32 = 2.;
+pub const BLINK_EVERY: f32 = 0.1;
+pub const COIN_POP_SPEED: f32 = 200.;
+pub const COIN_POP_TIME: f32 = 0.4;
+pub const JUMP_CUT: f32 = 0.45;
+pub const COYOTE_TIME: f32 = 0.1;
+pub const JUMP_BUFFER_TIME: f32 = 0.12;
+pub const FIXED_STEP: f32 = 1. / 120.;
+pub const MAX_FRAME_TIME: f32 = 0.25;
+pub const STOMP_POINTS: u32 = 100;
+pub const STOMP_BOUNCE: f32 = 120.;
+pub const LEVEL_TIME: f32 = 300.;
+pub const TIME_WARNING: f32 = 100.;
+pub const POPUP_TIME: f32 = 0.8;
+pub const POPUP_SPEED: f32 = 30.;
+pub const CAMERA_DEAD_ZONE: f32 = 24.;
+pub const CAMERA_LOOK_AHEAD: f32 = 48.;
+pub const CAMERA_SMOOTHING: f32 = 8.;
+pub const SHELL_SPEED: f32 = 160.;
+pub const ENEMY_JUMP: f32 = 140.;
+pub const KICK_GRACE: f32 = 0.2;
+/// Where actors out of play wait, far above any map.
+pub const PARKED_POS: Vec2 = vec2(0., -10000.);
@@ -0,0 +1,389 @@
+use std::{cell::RefCell, rc::Rc};
+
+use macroquad::{
+    math::{Rect, Vec2, vec2},
+    texture::{Texture2D, load_texture},
+};
+use macroquad_platformer::{Actor, World};
+
+use crate::{
+    animation::{Animation, AnimationSet},
+    consts::{
+        ENEMY_FRAME_EVERY, ENEMY_JUMP, GRAVITY, KICK_GRACE, PLAYER_SIZE, SHELL_SPEED,
+        SPEED_ENEMY, SQUASHED_TIME,
+    },
+};
+
+/// How far apart the two walking frames are in `enemies.png`.
+const WALK_FRAME_STEP: f32 = 30.;
+
+/// Where a kind of enemy is in `enemies.png`: the first of its two walking
+/// frames, and the one for when it's down. Each sprite stands on the
+/// bottom of its frame.
+pub struct Frames {
+    pub walk: Rect,
+    pub down: Rect,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum EnemyKind {
+    Walker,
+    Shell,
+    Jumper,
+}
+
+impl EnemyKind {
+    pub fn from_name(name: &str) -> Option<EnemyKind> {
+        match name {
+            "enemy" | "walker" => Some(EnemyKind::Walker),
+            "shell" => Some(EnemyKind::Shell),
+            "jumper" => Some(EnemyKind::Jumper),
+            _ => None,
+        }
+    }
+
+    fn behaviour(self) -> Box<dyn Behaviour> {
+        match self {
+            EnemyKind::Walker => Box::new(Walker),
+            EnemyKind::Shell => Box::new(ShellWalker),
+            EnemyKind::Jumper => Box::new(Jumper),
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum EnemyState {
+    Walk,
+    Squashed,
+    /// In its shell, standing still.
+    Shell,
+    /// A shell sliding after a kick.
+    Kicked,
+    /// Knocked out by a kicked shell.
+    Dead,
+}
+
+/// What makes one kind of enemy different from another.
+pub trait Behaviour {
+    fn frames(&self) -> Frames;
+
+    /// Steers the body while walking.
+    fn walk(&mut self, body: &mut Body);
+
+    /// The state a stomp leaves it in.
+    fn stomped(&self, state: EnemyState) -> EnemyState;
+}
+
+/// Walks back and forth, turning at walls and at the edge of ledges.
+pub struct Walker;
+
+impl Behaviour for Walker {
+    fn frames(&self) -> Frames {
+        Frames {
+            walk: Rect::new(0., 4., 16., 16.),
+            down: Rect::new(60., 0., 16., 16.),
+        }
+    }
+
+    fn walk(&mut self, body: &mut Body) {
+        if body.wall_ahead() || body.ledge_ahead() {
+            body.turn();
+        }
+    }
+
+    fn stomped(&self, _state: EnemyState) -> EnemyState {
+        EnemyState::Squashed
+    }
+}
+
+/// Walks like a walker and hides in its shell when stomped. The shell can
+/// be kicked into other enemies.
+pub struct ShellWalker;
+
+impl Behaviour for ShellWalker {
+    fn frames(&self) -> Frames {
+        Frames {
+            walk: Rect::new(90., 0., 16., 24.),
+            down: Rect::new(330., 4., 16., 16.),
+        }
+    }
+
+    fn walk(&mut self, body: &mut Body) {
+        Walker.walk(body);
+    }
+
+    fn stomped(&self, state: EnemyState) -> EnemyState {
+        shell_stomped(state)
+    }
+}
+
+/// Hops along, turning only at walls. Drops into its shell when stomped.
+pub struct Jumper;
+
+impl Behaviour for Jumper {
+    fn frames(&self) -> Frames {
+        Frames {
+            walk: Rect::new(90., 60., 16., 24.),
+            down: Rect::new(330., 64., 16., 16.),
+        }
+    }
+
+    fn walk(&mut self, body: &mut Body) {
+        if body.wall_ahead() {
+            body.turn();
+        }
+
+        if body.is_on_ground() {
+            body.speed.y = -ENEMY_JUMP;
+        }
+    }
+
+    fn stomped(&self, state: EnemyState) -> EnemyState {
+        shell_stomped(state)
+    }
+}
+
+/// A stomp puts a walking or sliding shell to rest, and kicks a resting one.
+fn shell_stomped(state: EnemyState) -> EnemyState {
+    match state {
+        EnemyState::Shell => EnemyState::Kicked,
+        _ => EnemyState::Shell,
+    }
+}
+
+/// The actor an enemy moves in the world.
+pub struct Body {
+    world: Rc<RefCell<World>>,
+    actor: Actor,
+    pub speed: Vec2,
+    prev_pos: Vec2,
+}
+
+impl Body {
+    pub fn pos(&self) -> Vec2 {
+        self.world.borrow().actor_pos(self.actor)
+    }
+
+    pub fn turn(&mut self) {
+        self.speed.x *= -1.;
+    }
+
+    fn front_x(&self) -> f32 {
+        let pos = self.pos();
+
+        if self.speed.x > 0. {
+            return pos.x + PLAYER_SIZE;
+        }
+
+        pos.x - 1.
+    }
+
+    pub fn wall_ahead(&self) -> bool {
+        let pos = self.pos();
+
+        self.world
+            .borrow()
+            .solid_at(vec2(self.front_x(), pos.y + PLAYER_SIZE / 2.))
+    }
+
+    /// Standing on the ground with nothing under the next step.
+    pub fn ledge_ahead(&self) -> bool {
+        let pos = self.pos();
+        let below = vec2(self.front_x(), pos.y + PLAYER_SIZE + 1.);
+
+        self.is_on_ground() && !self.world.borrow().solid_at(below)
+    }
+
+    pub fn is_on_ground(&self) -> bool {
+        let world = self.world.borrow();
+
+        world.collide_check(self.actor, self.pos() + vec2(0., 1.))
+    }
+
+    fn mov(&mut self, dt: f32) {
+        let mut world = self.world.borrow_mut();
+
+        world.move_v(self.actor, self.speed.y * dt);
+
+        if !world.move_h(self.actor, self.speed.x * dt) {
+            self.speed.x *= -1.;
+        }
+    }
+
+    fn mov_gravity(&mut self, dt: f32) {
+        if self.is_on_ground() && self.speed.y >= 0. {
+            self.speed.y = 0.;
+            return;
+        }
+
+        self.speed.y += GRAVITY * dt;
+    }
+}
+
+pub struct Enemy {
+    body: Body,
+    behaviour: Box<dyn Behaviour>,
+    animations: AnimationSet<EnemyState>,
+    state_time: f32,
+}
+
+impl Enemy {
+
+    pub async fn new(kind: EnemyKind, pos: Vec2, world: Rc<RefCell<World>>) -> Self {
+        let texture = load_texture("./assets/enemies.png")
+            .await
+            .unwrap();
+
+        let actor = world
+            .borrow_mut()
+            .add_actor(pos, PLAYER_SIZE as i32, PLAYER_SIZE as i32);
+
+        let behaviour = kind.behaviour();
+
+        Enemy {
+            body: Body {
+                world,
+                actor,
+                speed: vec2(-SPEED_ENEMY, 0.),
+                prev_pos: pos,
+            },
+            animations: Enemy::animations(texture, behaviour.frames()),
+            behaviour,
+            state_time: 0.,
+        }
+    }
+
+    fn animations(texture: Texture2D, frames: Frames) -> AnimationSet<EnemyState> {
+        let walk = frames.walk;
+        let down = || Animation::still(frames.down);
+
+        AnimationSet::new(texture, EnemyState::Walk)
+            .with(EnemyState::Walk, Animation::strip(walk.point(), walk.size(), WALK_FRAME_STEP, 2, ENEMY_FRAME_EVERY))
+            .with(EnemyState::Squashed, down())
+            .with(EnemyState::Shell, down())
+            .with(EnemyState::Kicked, down())
+            .with(EnemyState::Dead, down())
+    }
+
+    pub fn state(&self) -> EnemyState {
+        self.animations.state()
+    }
+
+    fn set_state(&mut self, state: EnemyState) {
+        self.animations.set_state(state);
+        self.state_time = 0.;
+    }
+
+    /// Landed on by the player, who is at `from_x`.
+    pub fn stomp(&mut self, from_x: f32) {
+        let state = self.behaviour.stomped(self.state());
+
+        match state {
+            EnemyState::Kicked => self.kick(from_x),
+            _ => {
+                self.body.speed = vec2(0., 0.);
+                self.set_state(state);
+            }
+        }
+    }
+
+    /// Sends a resting shell sliding away from `from_x`.
+    pub fn kick(&mut self, from_x: f32) {
+        let direction = if self.area().center().x >= from_x { 1. } else { -1. };
+
+        self.body.speed = vec2(direction * SHELL_SPEED, 0.);
+        self.set_state(EnemyState::Kicked);
+    }
+
+    /// Hit by a sliding shell.
+    pub fn knock_out(&mut self) {
+        self.body.speed = vec2(0., 0.);
+        self.animations.flip_y = true;
+        self.set_state(EnemyState::Dead);
+    }
+
+    /// Walks away from another enemy it bumped into.
+    pub fn turn_away_from(&mut self, x: f32) {
+        let away = if self.area().center().x < x { -1. } else { 1. };
+
+        self.body.speed.x = away * self.body.speed.x.abs();
+    }
+
+    /// Whether it can be stomped on.
+    pub fn is_stompable(&self) -> bool {
+        matches!(self.state(), EnemyState::Walk | EnemyState::Shell | EnemyState::Kicked)
+    }
+
+    /// Whether touching it from the side hurts. A shell that was just
+    /// kicked gives the player a moment to get out of the way.
+    pub fn is_harmful(&self) -> bool {
+        match self.state() {
+            EnemyState::Walk => true,
+            EnemyState::Kicked => self.state_time > KICK_GRACE,
+            _ => false,
+        }
+    }
+
+    pub fn is_resting_shell(&self) -> bool {
+        self.state() == EnemyState::Shell
+    }
+
+    /// A shell that was just stomped stays put while the player bounces
+    /// off it.
+    pub fn is_kickable(&self) -> bool {
+        self.is_resting_shell() && self.state_time > KICK_GRACE
+    }
+
+    pub fn is_walking(&self) -> bool {
+        self.state() == EnemyState::Walk
+    }
+
+    /// Squashed or knocked out long enough to be taken off the map.
+    pub fn is_gone(&self) -> bool {
+        matches!(self.state(), EnemyState::Squashed | EnemyState::Dead)
+            && self.state_time >= SQUASHED_TIME
+    }
+
+    pub fn update(&mut self, dt: f32) {
+        self.body.prev_pos = self.body.pos();
+        self.animations.update(dt);
+        self.state_time += dt;
+
+        match self.state() {
+            EnemyState::Walk => self.behaviour.walk(&mut self.body),
+            EnemyState::Kicked => {}
+            // Stopped, but still falls when it was stomped in the air.
+            EnemyState::Shell | EnemyState::Squashed => self.body.speed.x = 0.,
+            EnemyState::Dead => return,
+        }
+
+        self.body.mov(dt);
+        self.body.mov_gravity(dt);
+
+        // The sprites face left.
+        if self.body.speed.x != 0. {
+            self.animations.flip_x = self.body.speed.x > 0.;
+        }
+    }
+
+    pub fn draw(&self, alpha: f32) {
+        let pos = self.body.prev_pos.lerp(self.pos(), alpha);
+
+        let height = self.animations.frame().map_or(PLAYER_SIZE, |frame| frame.h);
+        self.animations.draw(pos - vec2(0., height - PLAYER_SIZE));
+    }
+
+    pub fn pos(&self) -> Vec2 {
+        self.body.pos()
+    }
+
+    pub fn area(&self) -> Rect {
+        let pos = self.pos();
+        Rect {
+            x: pos.x,
+            y: pos.y,
+            w: PLAYER_SIZE,
+            h: PLAYER_SIZE,
+        }
+    }
+}
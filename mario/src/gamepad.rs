@@ -0,0 +1,122 @@
+use std::collections::HashMap;
+
+/// Stick travel, out of 32767, before an axis counts as pushed.
+const AXIS_THRESHOLD: i16 = 16384;
+
+/// Frames between looks for a controller while none is plugged in.
+const RESCAN_EVERY: u32 = 60;
+
+/// Button and axis state of the first controller found. Neither macroquad
+/// nor miniquad report gamepads yet, so on Linux this reads the kernel
+/// joystick device directly. Elsewhere it says so once and never sees a
+/// controller.
+pub struct Gamepad {
+    device: Option<std::fs::File>,
+    buttons: HashMap<u8, bool>,
+    axes: HashMap<u8, i16>,
+    rescan: u32,
+}
+
+impl Gamepad {
+    pub fn new() -> Self {
+        #[cfg(not(target_os = "linux"))]
+        println!("No controller support on this platform, only the keyboard works");
+
+        Gamepad {
+            device: None,
+            buttons: HashMap::new(),
+            axes: HashMap::new(),
+            rescan: 0,
+        }
+    }
+
+    pub fn is_button_down(&self, button: u8) -> bool {
+        self.buttons.get(&button).copied().unwrap_or(false)
+    }
+
+    /// Whether the axis is pushed past the threshold towards `direction`,
+    /// which is -1 or 1.
+    pub fn is_axis_pushed(&self, axis: u8, direction: i16) -> bool {
+        let value = self.axes.get(&axis).copied().unwrap_or(0) as i32;
+        value * direction as i32 > AXIS_THRESHOLD as i32
+    }
+
+    /// Drains the events since the last frame. Call once per frame.
+    pub fn update(&mut self) {
+        if self.device.is_none() {
+            if self.rescan > 0 {
+                self.rescan -= 1;
+                return;
+            }
+
+            self.rescan = RESCAN_EVERY;
+            self.device = open_device();
+        }
+
+        self.read_events();
+    }
+
+    #[cfg(target_os = "linux")]
+    fn read_events(&mut self) {
+        use std::io::{ErrorKind, Read};
+
+        const JS_EVENT_BUTTON: u8 = 0x01;
+        const JS_EVENT_AXIS: u8 = 0x02;
+        const JS_EVENT_INIT: u8 = 0x80;
+
+        let Some(device) = &mut self.device else {
+            return;
+        };
+
+        // struct js_event { u32 time; i16 value; u8 type; u8 number; }
+        let mut event = [0u8; 8];
+
+        loop {
+            match device.read_exact(&mut event) {
+                Ok(()) => {
+                    let value = i16::from_ne_bytes([event[4], event[5]]);
+                    let number = event[7];
+
+                    match event[6] & !JS_EVENT_INIT {
+                        JS_EVENT_BUTTON => {
+                            self.buttons.insert(number, value != 0);
+                        }
+                        JS_EVENT_AXIS => {
+                            self.axes.insert(number, value);
+                        }
+                        _ => {}
+                    }
+                }
+                Err(e) if e.kind() == ErrorKind::WouldBlock => return,
+                Err(_) => {
+                    // Unplugged: forget the state so nothing stays held down.
+                    self.device = None;
+                    self.buttons.clear();
+                    self.axes.clear();
+                    return;
+                }
+            }
+        }
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    fn read_events(&mut self) {}
+}
+
+#[cfg(target_os = "linux")]
+fn open_device() -> Option<std::fs::File> {
+    use std::os::unix::fs::OpenOptionsExt;
+
+    (0..4).find_map(|i| {
+        std::fs::OpenOptions::new()
+            .read(true)
+            .custom_flags(libc::O_NONBLOCK)
+            .open(format!("/dev/input/js{}", i))
+            .ok()
+    })
+}
+
+#[cfg(not(target_os = "linux"))]
+fn open_device() -> Option<std::fs::File> {
+    None
+}
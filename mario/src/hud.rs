@@ -0,0 +1,45 @@
+use macroquad::{
+    camera::set_default_camera,
+    color::{WHITE, YELLOW},
+    text::draw_text,
+    window::screen_width,
+};
+
+use crate::consts::TIME_WARNING;
+
+const FONT_SIZE: f32 = 30.;
+
+/// The numbers across the top of the screen.
+pub struct Hud<'a> {
+    pub score: u32,
+    pub coins: u32,
+    pub world: &'a str,
+    pub time: f32,
+    pub lives: u32,
+}
+
+impl Hud<'_> {
+    /// Switches to screen space, so it has to come after the world.
+    pub fn draw(&self) {
+        set_default_camera();
+
+        let time = self.time.max(0.).ceil() as u32;
+        let columns = [
+            ("SCORE", format!("{:06}", self.score)),
+            ("COINS", format!("x{:02}", self.coins)),
+            ("WORLD", self.world.to_string()),
+            ("TIME", format!("{:03}", time)),
+            ("LIVES", format!("x{}", self.lives)),
+        ];
+
+        let width = screen_width() / columns.len() as f32;
+
+        for (i, (label, value)) in columns.iter().enumerate() {
+            let x = 20. + i as f32 * width;
+            let color = if *label == "TIME" && self.time < TIME_WARNING { YELLOW } else { WHITE };
+
+            draw_text(label, x, 30., FONT_SIZE, WHITE);
+            draw_text(value, x, 30. + FONT_SIZE, FONT_SIZE, color);
+        }
+    }
+}
@@ -0,0 +1,241 @@
+use macroquad::{
+    file::load_string,
+    input::{is_key_down, is_key_pressed, KeyCode},
+};
+
+use crate::gamepad::Gamepad;
+
+const CONTROLS: &str = "./assets/controls.txt";
+
+/// What the player wants to do, whatever they pressed to do it.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Action {
+    MoveLeft,
+    MoveRight,
+    Jump,
+    Pause,
+    MenuUp,
+    MenuDown,
+    Confirm,
+}
+
+const ACTIONS: [Action; 7] = [
+    Action::MoveLeft,
+    Action::MoveRight,
+    Action::Jump,
+    Action::Pause,
+    Action::MenuUp,
+    Action::MenuDown,
+    Action::Confirm,
+];
+
+impl Action {
+    pub fn from_name(name: &str) -> Option<Action> {
+        match name {
+            "left" => Some(Action::MoveLeft),
+            "right" => Some(Action::MoveRight),
+            "jump" => Some(Action::Jump),
+            "pause" => Some(Action::Pause),
+            "up" => Some(Action::MenuUp),
+            "down" => Some(Action::MenuDown),
+            "confirm" => Some(Action::Confirm),
+            _ => None,
+        }
+    }
+}
+
+/// A key, a gamepad button, or one direction of a gamepad axis.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Binding {
+    Key(KeyCode),
+    Button(u8),
+    Axis(u8, i16),
+}
+
+impl Binding {
+    /// `Left`, `A`, `Space`... for keys, `pad:0` for button 0 and
+    /// `axis:0-` / `axis:0+` for the two directions of axis 0.
+    pub fn from_name(name: &str) -> Option<Binding> {
+        if let Some(button) = name.strip_prefix("pad:") {
+            return button.parse().ok().map(Binding::Button);
+        }
+
+        if let Some(axis) = name.strip_prefix("axis:") {
+            let (number, direction) = if let Some(n) = axis.strip_suffix('-') {
+                (n, -1)
+            } else if let Some(n) = axis.strip_suffix('+') {
+                (n, 1)
+            } else {
+                return None;
+            };
+
+            return number.parse().ok().map(|n| Binding::Axis(n, direction));
+        }
+
+        key_from_name(name).map(Binding::Key)
+    }
+
+    fn is_down(&self, gamepad: &Gamepad) -> bool {
+        match *self {
+            Binding::Key(key) => is_key_down(key),
+            Binding::Button(button) => gamepad.is_button_down(button),
+            Binding::Axis(axis, direction) => gamepad.is_axis_pushed(axis, direction),
+        }
+    }
+
+    /// Keys also count when tapped and let go between two frames, which
+    /// comparing `is_down` across frames would miss.
+    fn is_tapped(&self) -> bool {
+        match *self {
+            Binding::Key(key) => is_key_pressed(key),
+            _ => false,
+        }
+    }
+}
+
+/// Maps actions to keys and gamepad inputs. Call `update` once at the
+/// start of every frame, then ask about actions instead of keys.
+pub struct Input {
+    bindings: Vec<(Action, Binding)>,
+    gamepad: Gamepad,
+    down: Vec<Action>,
+    was_down: Vec<Action>,
+    pressed: Vec<Action>,
+}
+
+impl Input {
+    pub async fn load() -> Self {
+        let controls = load_string(CONTROLS)
+            .await
+            .unwrap();
+
+        Input {
+            bindings: Input::parse(&controls),
+            gamepad: Gamepad::new(),
+            down: Vec::new(),
+            was_down: Vec::new(),
+            pressed: Vec::new(),
+        }
+    }
+
+    /// One action per line followed by everything bound to it, e.g.
+    /// `jump Space pad:0`. Blank lines and lines starting with `#` are
+    /// skipped.
+    pub fn parse(controls: &str) -> Vec<(Action, Binding)> {
+        let mut bindings = Vec::new();
+
+        let lines = controls
+            .lines()
+            .map(|line| line.trim())
+            .filter(|line| !line.is_empty() && !line.starts_with('#'));
+
+        for line in lines {
+            let mut parts = line.split_whitespace();
+
+            let Some(action) = parts.next().and_then(Action::from_name) else {
+                println!("Skipping controls '{}' in {}", line, CONTROLS);
+                continue;
+            };
+
+            for name in parts {
+                match Binding::from_name(name) {
+                    Some(binding) => bindings.push((action, binding)),
+                    None => println!("Skipping binding '{}' in {}", name, CONTROLS),
+                }
+            }
+        }
+
+        bindings
+    }
+
+    pub fn update(&mut self) {
+        self.gamepad.update();
+
+        self.was_down = std::mem::take(&mut self.down);
+        self.down = ACTIONS
+            .into_iter()
+            .filter(|action| self.is_bound_down(*action))
+            .collect();
+
+        self.pressed = ACTIONS
+            .into_iter()
+            .filter(|action| {
+                let newly_down = self.is_down(*action) && !self.was_down.contains(action);
+                newly_down || self.is_bound_tapped(*action)
+            })
+            .collect();
+    }
+
+    pub fn is_down(&self, action: Action) -> bool {
+        self.down.contains(&action)
+    }
+
+    pub fn is_pressed(&self, action: Action) -> bool {
+        self.pressed.contains(&action)
+    }
+
+    pub fn is_released(&self, action: Action) -> bool {
+        !self.is_down(action) && self.was_down.contains(&action)
+    }
+
+    fn is_bound_down(&self, action: Action) -> bool {
+        self.bindings
+            .iter()
+            .any(|(a, binding)| *a == action && binding.is_down(&self.gamepad))
+    }
+
+    fn is_bound_tapped(&self, action: Action) -> bool {
+        self.bindings
+            .iter()
+            .any(|(a, binding)| *a == action && binding.is_tapped())
+    }
+}
+
+fn key_from_name(name: &str) -> Option<KeyCode> {
+    let key = match name {
+        "Left" => KeyCode::Left,
+        "Right" => KeyCode::Right,
+        "Up" => KeyCode::Up,
+        "Down" => KeyCode::Down,
+        "Space" => KeyCode::Space,
+        "Enter" => KeyCode::Enter,
+        "Escape" => KeyCode::Escape,
+        "Tab" => KeyCode::Tab,
+        "Backspace" => KeyCode::Backspace,
+        "LeftShift" => KeyCode::LeftShift,
+        "RightShift" => KeyCode::RightShift,
+        "LeftControl" => KeyCode::LeftControl,
+        "RightControl" => KeyCode::RightControl,
+        "LeftAlt" => KeyCode::LeftAlt,
+        "RightAlt" => KeyCode::RightAlt,
+        "A" => KeyCode::A,
+        "B" => KeyCode::B,
+        "C" => KeyCode::C,
+        "D" => KeyCode::D,
+        "E" => KeyCode::E,
+        "F" => KeyCode::F,
+        "G" => KeyCode::G,
+        "H" => KeyCode::H,
+        "I" => KeyCode::I,
+        "J" => KeyCode::J,
+        "K" => KeyCode::K,
+        "L" => KeyCode::L,
+        "M" => KeyCode::M,
+        "N" => KeyCode::N,
+        "O" => KeyCode::O,
+        "P" => KeyCode::P,
+        "Q" => KeyCode::Q,
+        "R" => KeyCode::R,
+        "S" => KeyCode::S,
+        "T" => KeyCode::T,
+        "U" => KeyCode::U,
+        "V" => KeyCode::V,
+        "W" => KeyCode::W,
+        "X" => KeyCode::X,
+        "Y" => KeyCode::Y,
+        "Z" => KeyCode::Z,
+        _ => return None,
+    };
+
+    Some(key)
+}
@@ -0,0 +1,47 @@
+use macroquad::file::load_string;
+
+const MANIFEST: &str = "./assets/levels.txt";
+
+/// A level from the manifest. Files are relative to `assets`.
+pub struct Level {
+    pub name: String,
+    pub map: String,
+    pub tileset: String,
+}
+
+impl Level {
+    pub async fn load_all() -> Vec<Level> {
+        let manifest = load_string(MANIFEST)
+            .await
+            .unwrap();
+
+        let levels = Level::parse(&manifest);
+        assert!(!levels.is_empty(), "No levels in {}", MANIFEST);
+
+        levels
+    }
+
+    /// One level per line: `name map tileset`. Blank lines and lines
+    /// starting with `#` are skipped.
+    pub fn parse(manifest: &str) -> Vec<Level> {
+        manifest
+            .lines()
+            .map(|line| line.trim())
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .filter_map(|line| {
+                let parts: Vec<&str> = line.split_whitespace().collect();
+
+                if parts.len() != 3 {
+                    println!("Skipping level '{}' in {}", line, MANIFEST);
+                    return None;
+                }
+
+                Some(Level {
+                    name: parts[0].to_string(),
+                    map: parts[1].to_string(),
+                    tileset: parts[2].to_string(),
+                })
+            })
+            .collect()
+    }
+}
@@ -1,94 +1,277 @@
+mod animation;
 mod sounds;
 mod consts;
+mod enemies;
+mod gamepad;
+mod hud;
+mod input;
+mod levels;
 mod map;
+mod menu;
 mod sprites;
-use std::{cell::{Ref, RefCell}, rc::Rc};
+mod state;
+use std::{cell::RefCell, collections::HashMap, rc::Rc};
 
-use macroquad::{audio::Sound, prelude::*};
-use macroquad_platformer::{Tile, World};
+use fixed_clock::FixedClock;
+use follow_camera::FollowCamera;
+use macroquad::prelude::*;
+use macroquad_platformer::{Actor, Solid, Tile, World};
 
-use crate::{consts::{PLAYER_SIZE, TILE_GROUND, TILE_SIZE}, map::MapDrawer, sounds::Sounds, sprites::{Enemy, Player}};
+use crate::{consts::{CAMERA_DEAD_ZONE, CAMERA_LOOK_AHEAD, CAMERA_SMOOTHING, FIXED_STEP, LEVEL_CLEAR_DELAY, LEVEL_TIME, LIVES, MAX_FRAME_TIME, PLAYER_SIZE, RESPAWN_DELAY, STOMP_POINTS, TILE_SIZE}, enemies::{Enemy, EnemyState}, hud::Hud, input::{Action, Input}, levels::Level, map::{Contents, EntityKind, MapDrawer, TileKind}, menu::{Menu, MenuAction}, sounds::Sounds, sprites::{Coin, Mushroom, Player, PoppedCoin, ScorePopup}, state::GameState};
 
 
 struct Game {
+    levels: Vec<Level>,
+    level: usize,
     world: Rc<RefCell<World>>,
     map: MapDrawer,
     camera: Camera2D,
+    follow: FollowCamera,
     player: Player,
     enemies: Vec<Enemy>,
-    sounds: Sounds
+    coins: Vec<Coin>,
+    popped_coins: Vec<PoppedCoin>,
+    mushrooms: Vec<Mushroom>,
+    /// Actors of mushrooms gone out of play, parked for reuse.
+    spare_mushrooms: Vec<Actor>,
+    popups: Vec<ScorePopup>,
+    bricks: HashMap<(u32, u32), Solid>,
+    items: Texture2D,
+    sounds: Sounds,
+    lives: u32,
+    coin_count: u32,
+    score: u32,
+    time_left: f32,
+    start: Vec2,
+    checkpoint: Option<Vec2>,
+    dying: Option<f32>,
+    state: GameState,
+    menu: Menu,
+    clear_time: f32,
 }
 
 impl Game {
 
     pub async fn new() -> Self {
-        let map = MapDrawer::new()
+        let levels = Level::load_all().await;
+
+        let map = MapDrawer::new(&levels[0])
             .await;
 
         let camera = Game::create_camera(map.px_area());
+        let follow = Game::create_follow(map.px_area());
         let world = World::new();
         let r_world = Rc::new(RefCell::new(world));
 
-        let player = Player::new(r_world.clone())
+        let start = Game::find_start(&map);
+
+        let player = Player::new(start, r_world.clone())
             .await;
 
+        let items = load_texture("./assets/items.png")
+            .await
+            .unwrap();
+
         let sounds = Sounds::new().await;
 
         let mut game = Game{
+            levels,
+            level: 0,
             world: r_world.clone(),
-            map: map,
-            camera: camera,
-            player: player,
+            map,
+            camera,
+            follow,
+            player,
             enemies: Vec::new(),
-            sounds: sounds
+            coins: Vec::new(),
+            popped_coins: Vec::new(),
+            mushrooms: Vec::new(),
+            spare_mushrooms: Vec::new(),
+            popups: Vec::new(),
+            bricks: HashMap::new(),
+            items,
+            sounds,
+            lives: LIVES,
+            coin_count: 0,
+            score: 0,
+            time_left: LEVEL_TIME,
+            start,
+            checkpoint: None,
+            dying: None,
+            state: GameState::Title,
+            menu: Menu::title(),
+            clear_time: 0.,
         };
 
 
         game.add_enemies().await;
+        game.add_coins();
         game.load_tiles();
+        game.follow.snap(start);
+        game
+    }
+
+    pub async fn restart(&mut self) {
+        self.lives = LIVES;
+        self.coin_count = 0;
+        self.score = 0;
+        self.load_level(0).await;
+    }
+
+    /// Swaps in a new map with a fresh world, player and enemies.
+    pub async fn load_level(&mut self, level: usize) {
+        self.level = level;
+        self.map = MapDrawer::new(&self.levels[level]).await;
+        self.camera = Game::create_camera(self.map.px_area());
+        self.follow = Game::create_follow(self.map.px_area());
+        self.world = Rc::new(RefCell::new(World::new()));
+
+        self.start = Game::find_start(&self.map);
+        self.player = Player::new(self.start, self.world.clone()).await;
+        self.checkpoint = None;
+        self.dying = None;
+        self.state = GameState::Playing;
+        self.time_left = LEVEL_TIME;
+
+        self.enemies.clear();
+        self.popped_coins.clear();
+        self.mushrooms.clear();
+        self.spare_mushrooms.clear();
+        self.add_enemies().await;
+        self.add_coins();
+        self.load_tiles();
+        self.follow.snap(self.start);
+        self.sounds.play("background");
+    }
+
+    pub async fn next_level(&mut self) {
+        self.load_level(self.level + 1).await;
+    }
+
+    pub fn is_last_level(&self) -> bool {
+        self.level + 1 >= self.levels.len()
+    }
+
+    /// The clear screen has been shown long enough to move on.
+    pub fn is_level_done(&self) -> bool {
+        self.state == GameState::LevelComplete && self.clear_time <= 0. && !self.is_last_level()
+    }
+
+    pub fn is_all_clear(&self) -> bool {
+        self.state == GameState::LevelComplete && self.is_last_level()
+    }
 
-        game.sounds.play_background();
-        return game;
+    /// The title and pause screens hold the world still.
+    pub fn is_frozen(&self) -> bool {
+        matches!(self.state, GameState::Title | GameState::Paused)
     }
 
-    pub fn load_tiles(&self) {
+    /// Whether keys should go to the menu instead of the player.
+    pub fn has_menu(&self) -> bool {
+        match self.state {
+            GameState::Title | GameState::Paused | GameState::GameOver => true,
+            GameState::LevelComplete => self.is_all_clear(),
+            GameState::Playing => false,
+        }
+    }
+
+    /// Stops the music and the world until `resume`.
+    pub fn pause(&mut self) {
+        if self.state != GameState::Playing {
+            return;
+        }
+
+        self.state = GameState::Paused;
+        self.menu = Menu::paused();
+        self.player.stop();
+        self.sounds.pause_music();
+    }
+
+    pub fn resume(&mut self) {
+        if self.state != GameState::Paused {
+            return;
+        }
+
+        self.state = GameState::Playing;
+        self.sounds.resume_music();
+    }
+
+    pub fn show_title_screen(&mut self) {
+        self.state = GameState::Title;
+        self.menu = Menu::title();
+        self.sounds.stop_music();
+    }
+
+    /// Carries out a menu pick. Returns false when the player asked to quit.
+    pub async fn choose(&mut self, action: MenuAction) -> bool {
+        match action {
+            MenuAction::Start | MenuAction::Restart => self.restart().await,
+            MenuAction::Resume => self.resume(),
+            MenuAction::Title => self.show_title_screen(),
+            MenuAction::Quit => return false,
+        }
+
+        true
+    }
+
+    pub fn find_start(map: &MapDrawer) -> Vec2 {
+        map.entities_of(EntityKind::Player)
+            .next()
+            .map(|e| Game::spawn_pos(e.area))
+            .unwrap_or(Player::start_pos())
+    }
+
+    /// Puts an actor on the bottom of an entity's area.
+    pub fn spawn_pos(area: Rect) -> Vec2 {
+        vec2(area.x, area.y + area.h - PLAYER_SIZE)
+    }
+
+    /// Coins can be walked through and bricks can be broken, so neither
+    /// goes into the static layer. Bricks become solids of their own.
+    pub fn load_tiles(&mut self) {
         let tiles = self.map.get_tiles();
         let mut world = self.world.borrow_mut();
+        self.bricks.clear();
 
         let obstacles:Vec<Tile> = tiles
-            .map(|i| {
-                if i.2.is_some() {
-                    return Tile::Solid;
+            .map(|(x, y, tile)| {
+                let Some(tile) = tile else {
+                    return Tile::Empty;
+                };
+
+                match self.map.tile_kind(tile) {
+                    TileKind::Coin => Tile::Empty,
+                    TileKind::Brick => {
+                        let pos = vec2(x as f32 * TILE_SIZE, y as f32 * TILE_SIZE);
+                        let solid = world.add_solid(pos, TILE_SIZE as i32, TILE_SIZE as i32);
+                        self.bricks.insert((x, y), solid);
+                        Tile::Empty
+                    }
+                    _ => Tile::Solid,
                 }
-                return Tile::Empty
             }).collect();
 
         world.add_static_tiled_layer(
-            obstacles, 
+            obstacles,
             self.map.tile_width(),
-            self.map.tile_height(), 
+            self.map.tile_height(),
             self.map.width() as usize, 1);
 
         drop(world);
     }
 
     pub async fn add_enemies(&mut self) {
-        let y = TILE_GROUND * TILE_SIZE - PLAYER_SIZE;
         let world = self.world.clone();
 
-        let mut x = vec![];
-
-        x.push(400.);
-        x.push(550.);
-        x.push(800.);
-        x.push(1400.);
-        x.push(1600.);
+        for e in self.map.entities() {
+            let EntityKind::Enemy(kind) = e.kind else {
+                continue;
+            };
 
-        for ix in x {
-            let position = vec2(ix, y);
+            let position = Game::spawn_pos(e.area);
 
             if !world.borrow().solid_at(position) {
-                let enemy = Enemy::new(position, self.world.clone())
+                let enemy = Enemy::new(kind, position, self.world.clone())
                 .await;
                 self.enemies.push(enemy);
 
@@ -96,9 +279,121 @@ impl Game {
         }
     }
 
-    pub fn draw_enemies(&mut self) {
+    pub fn add_coins(&mut self) {
+        self.coins = self.map.entities_of(EntityKind::Coin)
+            .map(|e| Coin::new(vec2(e.area.x, e.area.y), self.items.clone()))
+            .collect();
+    }
+
+    pub fn update_enemies(&mut self, dt: f32) {
         for e in &mut self.enemies {
-            e.draw();
+            e.update(dt);
+        }
+
+        self.collide_enemies();
+
+        let height = self.map.px_height();
+        self.enemies.retain(|e| !e.is_gone() && e.pos().y <= height);
+    }
+
+    /// Actors in the world pass through each other, so enemies bump here:
+    /// walkers turn away from each other and a kicked shell knocks out
+    /// whatever it runs into.
+    pub fn collide_enemies(&mut self) {
+        let mut knocked_out = vec![];
+
+        for i in 0..self.enemies.len() {
+            for j in i + 1..self.enemies.len() {
+                let (a, b) = self.enemies.split_at_mut(j);
+                let (a, b) = (&mut a[i], &mut b[0]);
+
+                if !a.area().overlaps(&b.area()) {
+                    continue;
+                }
+
+                let a_kicked = a.state() == EnemyState::Kicked;
+                let b_kicked = b.state() == EnemyState::Kicked;
+
+                if a_kicked && !b_kicked && b.state() != EnemyState::Dead {
+                    b.knock_out();
+                    knocked_out.push(b.pos());
+                } else if b_kicked && !a_kicked && a.state() != EnemyState::Dead {
+                    a.knock_out();
+                    knocked_out.push(a.pos());
+                } else if a.is_walking() && b.is_walking() {
+                    let (ax, bx) = (a.area().center().x, b.area().center().x);
+                    a.turn_away_from(bx);
+                    b.turn_away_from(ax);
+                }
+            }
+        }
+
+        for pos in knocked_out {
+            self.sounds.play("kick");
+            self.add_score(STOMP_POINTS, pos);
+        }
+    }
+
+    pub fn update_coins(&mut self, dt: f32) {
+        for c in &mut self.coins {
+            c.update(dt);
+        }
+
+        for c in &mut self.popped_coins {
+            c.update(dt);
+        }
+
+        self.popped_coins.retain(|c| !c.is_gone());
+    }
+
+    pub fn update_popups(&mut self, dt: f32) {
+        for p in &mut self.popups {
+            p.update(dt);
+        }
+
+        self.popups.retain(|p| !p.is_gone());
+    }
+
+    pub fn update_mushrooms(&mut self, dt: f32) {
+        for m in &mut self.mushrooms {
+            m.update(dt);
+        }
+
+        let height = self.map.px_height();
+        self.remove_mushrooms(|m| m.pos().y > height);
+    }
+
+    /// Parks the mushrooms `gone` picks out and returns how many.
+    pub fn remove_mushrooms(&mut self, gone: impl Fn(&Mushroom) -> bool) -> usize {
+        let removed: Vec<Mushroom> = self.mushrooms
+            .extract_if(.., |m| gone(m))
+            .collect();
+
+        let count = removed.len();
+        self.spare_mushrooms.extend(removed.into_iter().map(Mushroom::park));
+
+        count
+    }
+
+    pub fn draw_enemies(&self, alpha: f32) {
+        for e in &self.enemies {
+            e.draw(alpha);
+        }
+    }
+
+    pub fn draw_coins(&self, alpha: f32) {
+        for c in &self.coins {
+            c.draw();
+        }
+
+        for c in &self.popped_coins {
+            c.draw(alpha);
+        }
+    }
+
+    pub fn draw_mushrooms(&self, alpha: f32) {
+        for m in &self.mushrooms {
+            m.draw(alpha);
         }
     }
 
@@ -106,22 +401,112 @@ impl Game {
         let camera_area = Rect::new(
             0.,
             area.h,
-            area.w / 7., 
+            Game::view_width(area),
             -area.h);
 
-        let camera = Camera2D::from_display_rect(camera_area);
+        Camera2D::from_display_rect(camera_area)
 
-        return camera;
+    }
 
+    pub fn view_width(area: Rect) -> f32 {
+        area.w / 7.
     }
 
-    pub fn draw(&mut self) {
-        set_camera(&self.camera);
-        self.follow_camera();
-        self.map.draw();
-        self.player.draw();
-        self.draw_enemies();
+    /// The part of the map the camera shows.
+    pub fn view(&self) -> Rect {
+        let area = self.map.px_area();
+        let size = vec2(Game::view_width(area), area.h);
+        let corner = self.camera.target - size / 2.;
+
+        Rect::new(corner.x, corner.y, size.x, size.y)
+    }
+
+    /// Scrolls only forward, like the original game.
+    pub fn create_follow(area: Rect) -> FollowCamera {
+        FollowCamera::new(area, vec2(Game::view_width(area), area.h))
+            .with_dead_zone(vec2(CAMERA_DEAD_ZONE, area.h))
+            .with_offset(vec2(CAMERA_LOOK_AHEAD, 0.))
+            .with_smoothing(CAMERA_SMOOTHING)
+            .without_backtracking()
+    }
+
+    /// Advances the game by one fixed step.
+    pub fn update(&mut self, dt: f32) {
+        self.update_coins(dt);
+        self.update_mushrooms(dt);
+        self.update_enemies(dt);
+        self.update_popups(dt);
+
+        if self.state == GameState::GameOver {
+            return;
+        }
+
+        if self.state == GameState::LevelComplete {
+            self.clear_time -= dt;
+            return;
+        }
+
+        self.player.update(dt);
+        self.follow.update(self.player.pos(), dt);
+
+        if self.dying.is_some() {
+            self.handle_respawn(dt);
+            return;
+        }
+
+        self.handle_jump_sound();
+        self.handle_head_bump();
         self.handle_enemy_smashed();
+        self.handle_shell_kick();
+        self.handle_enemy_hit();
+        self.handle_fall();
+
+        if self.is_player_down() {
+            return;
+        }
+
+        self.handle_coins();
+        self.handle_tile_coins();
+        self.handle_mushrooms();
+        self.handle_checkpoint();
+        self.handle_goal();
+        self.handle_timer(dt);
+    }
+
+    /// Draws the world `alpha` of the way between the last two steps.
+    pub fn draw(&mut self, alpha: f32) {
+        clear_background(self.map.background());
+        self.follow_camera(alpha);
+        set_camera(&self.camera);
+        self.map.draw(self.view());
+        self.draw_coins(alpha);
+        self.draw_mushrooms(alpha);
+        self.draw_enemies(alpha);
+
+        if !matches!(self.state, GameState::GameOver | GameState::LevelComplete) {
+            self.player.draw(alpha);
+        }
+
+        for p in &self.popups {
+            p.draw(alpha);
+        }
+
+        match self.state {
+            GameState::Title => self.show_title(),
+            GameState::Playing => self.draw_hud(),
+            GameState::Paused => {
+                self.draw_hud();
+                self.show_paused();
+            }
+            GameState::LevelComplete => {
+                self.draw_hud();
+                self.show_level_complete();
+            }
+            GameState::GameOver => {
+                self.draw_hud();
+                self.show_game_over();
+            }
+        }
     }
 
     pub fn left(&mut self) {
@@ -137,36 +522,57 @@ impl Game {
     }
 
     pub fn jump(&mut self) {
+        if !self.is_playing() {
+            return;
+        }
+
         self.player.jump();
-        self.sounds.play_jump();
     }
 
-    pub fn follow_camera(&mut self) {
-        let height = self.map.px_height() / 2.;
-        let pos = self.player.pos();
+    pub fn release_jump(&mut self) {
+        self.player.release_jump();
+    }
 
-        self.camera.target = Vec2 {
-            x: pos.x + 200.,
-            y: height - 30. 
-        }
+    pub fn is_playing(&self) -> bool {
+        self.state == GameState::Playing && self.dying.is_none()
     }
 
-   pub fn handle_enemy_smashed(&mut self)  {
-       let enemy = self.get_enemy_smashed();
+    pub fn follow_camera(&mut self, alpha: f32) {
+        self.follow.apply(&mut self.camera, alpha);
+    }
 
-       if enemy.is_some() {
-           self.enemies.remove(enemy.unwrap());
+   pub fn handle_enemy_smashed(&mut self)  {
+       let Some(enemy) = self.get_enemy_smashed() else {
+           return;
+       };
+
+       let x = self.player.area().center().x;
+       let kicked = self.enemies[enemy].is_resting_shell();
+
+       self.enemies[enemy].stomp(x);
+       self.player.bounce();
+
+       // Kicking a shell by jumping on it scores nothing.
+       if kicked {
+           self.sounds.play("kick");
+       } else {
+           self.sounds.play("stomp");
+           self.add_score(STOMP_POINTS, self.enemies[enemy].pos());
        }
    }
 
    pub fn get_enemy_smashed(&self) -> Option<usize> {
+       if !self.player.is_falling() {
+           return None;
+       }
+
        let pp = self.player.bottom_center();
 
        for i in 0..self.enemies.len() {
            let e = &self.enemies[i];
            let e_area = e.area();
 
-           if e_area.contains(pp) {
+           if e.is_stompable() && e_area.contains(pp) {
                return Some(i);
            }
        }
@@ -174,41 +580,382 @@ impl Game {
        None
    }
 
+   /// Walking into a shell that is standing still kicks it.
+   pub fn handle_shell_kick(&mut self) {
+       let area = self.player.area();
+       let x = area.center().x;
+
+       for e in &mut self.enemies {
+           if e.is_kickable() && e.area().overlaps(&area) {
+               e.kick(x);
+               self.sounds.play("kick");
+           }
+       }
+   }
+
+   pub fn handle_enemy_hit(&mut self) {
+       if self.get_enemy_hit().is_none() {
+           return;
+       }
+
+       if self.player.is_big() {
+           self.player.shrink();
+           return;
+       }
+
+       self.kill_player();
+   }
+
+   /// An enemy touching the player from the side or from below.
+   pub fn get_enemy_hit(&self) -> Option<usize> {
+       if self.player.is_invincible() {
+           return None;
+       }
+
+       let area = self.player.area();
+       // A couple of pixels of slack so grazing an enemy isn't deadly.
+       let area = Rect::new(area.x + 2., area.y + 2., area.w - 4., area.h - 4.);
+
+       self.enemies
+           .iter()
+           .position(|e| e.is_harmful() && e.area().overlaps(&area))
+   }
+
+   pub fn handle_fall(&mut self) {
+       if self.player.pos().y > self.map.px_height() {
+           self.kill_player();
+       }
+   }
+
+   /// Adds points and shows them floating up from `pos`.
+   pub fn add_score(&mut self, points: u32, pos: Vec2) {
+       self.score += points;
+       self.popups.push(ScorePopup::new(pos, points));
+   }
+
+   /// Running out of time costs a life, like falling.
+   pub fn handle_timer(&mut self, dt: f32) {
+       if self.state != GameState::Playing || self.dying.is_some() {
+           return;
+       }
+
+       self.time_left -= dt;
+
+       if self.time_left <= 0. {
+           self.time_left = 0.;
+           self.kill_player();
+       }
+   }
+
+   pub fn handle_coins(&mut self) {
+       let area = self.player.area();
+       let before = self.coins.len();
+
+       self.coins.retain(|c| !c.area().overlaps(&area));
+
+       if self.coins.len() < before {
+           self.coin_count += (before - self.coins.len()) as u32;
+           self.sounds.play("coin");
+       }
+   }
+
+   pub fn handle_jump_sound(&mut self) {
+       if self.player.take_jumped() {
+           self.sounds.play("jump");
+       }
+   }
+
+   /// Coins drawn in the ground layer instead of placed as entities.
+   pub fn handle_tile_coins(&mut self) {
+       let area = self.player.area();
+       let x0 = (area.x / TILE_SIZE) as u32;
+       let y0 = (area.y.max(0.) / TILE_SIZE) as u32;
+       let x1 = ((area.x + area.w - 1.) / TILE_SIZE) as u32;
+       let y1 = ((area.y.max(0.) + area.h - 1.) / TILE_SIZE) as u32;
+
+       for x in x0..=x1 {
+           for y in y0..=y1 {
+               if self.map.kind_at(x, y) == Some(TileKind::Coin) {
+                   self.map.set_tile(x, y, None);
+                   self.coin_count += 1;
+                   self.sounds.play("coin");
+               }
+           }
+       }
+   }
+
+   /// Finds the block the player's head hit, trying the middle of the
+   /// head before its corners.
+   pub fn handle_head_bump(&mut self) {
+       if !self.player.take_bump() {
+           return;
+       }
+
+       let area = self.player.area();
+       let y = area.y - 1.;
+
+       if y < 0. {
+           return;
+       }
+
+       let y = (y / TILE_SIZE) as u32;
+
+       let block = [area.center().x, area.x + 1., area.x + area.w - 2.]
+           .into_iter()
+           .map(|x| (x / TILE_SIZE) as u32)
+           .find_map(|x| match self.map.kind_at(x, y) {
+               Some(kind @ (TileKind::Question(_) | TileKind::Brick)) => Some((x, y, kind)),
+               _ => None,
+           });
+
+       match block {
+           Some((x, y, TileKind::Question(contents))) => self.open_block(x, y, contents),
+           Some((x, y, TileKind::Brick)) if self.player.is_big() => self.break_brick(x, y),
+           Some(_) => self.sounds.play("bump"),
+           None => {}
+       }
+   }
+
+   pub fn open_block(&mut self, x: u32, y: u32, contents: Contents) {
+       let used = self.map.find_tile(TileKind::Used);
+       self.map.set_tile(x, y, used);
+
+       let above = vec2(x as f32 * TILE_SIZE, (y as f32 - 1.) * TILE_SIZE);
+
+       match contents {
+           Contents::Coin => {
+               self.coin_count += 1;
+               self.sounds.play("coin");
+               self.popped_coins.push(PoppedCoin::new(above, self.items.clone()));
+           }
+           Contents::Mushroom => {
+               self.sounds.play("bump");
+               let spare = self.spare_mushrooms.pop();
+               let mushroom = Mushroom::new(above, self.items.clone(), self.world.clone(), spare);
+               self.mushrooms.push(mushroom);
+           }
+       }
+   }
+
+   /// The world has no way to drop a solid, so a broken brick is moved
+   /// far above the map.
+   pub fn break_brick(&mut self, x: u32, y: u32) {
+       let Some(solid) = self.bricks.remove(&(x, y)) else {
+           return;
+       };
+
+       let mut world = self.world.borrow_mut();
+       let pos = world.solid_pos(solid);
+       world.solid_move(solid, 0., -pos.y - self.map.px_height());
+       drop(world);
+
+       self.map.set_tile(x, y, None);
+       self.sounds.play("break");
+   }
+
+   pub fn handle_mushrooms(&mut self) {
+       let area = self.player.area();
+
+       if self.remove_mushrooms(|m| m.area().overlaps(&area)) > 0 {
+           self.player.grow();
+           self.sounds.play("powerup");
+       }
+   }
+
+   pub fn handle_checkpoint(&mut self) {
+       let x = self.player.pos().x;
+
+       let reached = self.map.entities_of(EntityKind::Checkpoint)
+           .filter(|e| x >= e.area.x)
+           .max_by(|a, b| a.area.x.total_cmp(&b.area.x));
+
+       if let Some(e) = reached {
+           self.checkpoint = Some(Game::spawn_pos(e.area));
+       }
+   }
+
+   pub fn handle_goal(&mut self) {
+       if self.is_player_down() {
+           return;
+       }
+
+       let area = self.player.area();
+
+       let reached = self.map.entities_of(EntityKind::Goal)
+           .any(|e| e.area.overlaps(&area));
+
+       if reached {
+           self.player.stop();
+           self.state = GameState::LevelComplete;
+           self.menu = Menu::game_over();
+           self.clear_time = LEVEL_CLEAR_DELAY;
+           self.sounds.play("level_clear");
+       }
+   }
+
+   /// Dying, out of lives or done with the level: nothing else should
+   /// happen to the player this step.
+   pub fn is_player_down(&self) -> bool {
+       self.dying.is_some() || self.state != GameState::Playing
+   }
+
+   /// Does nothing when the player is already down, so two deaths in the
+   /// same step, like an enemy hit and a fall, only cost one life.
+   pub fn kill_player(&mut self) {
+       if self.is_player_down() {
+           return;
+       }
+
+       self.player.die();
+       self.lives = self.lives.saturating_sub(1);
+       self.sounds.play("death");
+
+       if self.lives == 0 {
+           self.state = GameState::GameOver;
+           self.menu = Menu::game_over();
+           return;
+       }
+
+       self.dying = Some(RESPAWN_DELAY);
+   }
+
+   pub fn handle_respawn(&mut self, dt: f32) {
+       let Some(time) = self.dying else {
+           return;
+       };
+
+       let time = time - dt;
+
+       if time > 0. {
+           self.dying = Some(time);
+           return;
+       }
+
+       let pos = self.checkpoint.unwrap_or(self.start);
+       self.player.respawn(pos);
+       self.follow.snap(pos);
+       self.dying = None;
+       self.time_left = LEVEL_TIME;
+       self.sounds.play("background");
+   }
+
+   pub fn draw_hud(&self) {
+       let hud = Hud {
+           score: self.score,
+           coins: self.coin_count,
+           world: &self.levels[self.level].name,
+           time: self.time_left,
+           lives: self.lives,
+       };
+
+       hud.draw();
+   }
+
+   pub fn show_level_complete(&self) {
+       set_default_camera();
+       let name = &self.levels[self.level].name;
+       let text = format!("WORLD {} CLEAR!", name);
+       draw_text(&text, screen_width() / 2. - 150., screen_height() / 2., 40., WHITE);
+
+       if self.is_last_level() {
+           draw_text("THANK YOU FOR PLAYING", screen_width() / 2. - 150., screen_height() / 2. + 40., 30., WHITE);
+           self.menu.draw(Menu::below_center() + 40.);
+       }
+
+       set_camera(&self.camera);
+   }
+
+   pub fn show_game_over(&self) {
+       set_default_camera();
+       draw_text("GAME OVER", screen_width() / 2. - 100., screen_height() / 2., 40., WHITE);
+       self.menu.draw(Menu::below_center());
+       set_camera(&self.camera);
+   }
+
+   pub fn show_title(&self) {
+       set_default_camera();
+       draw_text("SUPER MARIO", screen_width() / 2. - 120., screen_height() / 2., 50., WHITE);
+       self.menu.draw(Menu::below_center());
+       set_camera(&self.camera);
+   }
+
+   /// Dims the frozen level behind the pause menu.
+   pub fn show_paused(&self) {
+       set_default_camera();
+       draw_rectangle(0., 0., screen_width(), screen_height(), Color::new(0., 0., 0., 0.5));
+       draw_text("PAUSED", screen_width() / 2. - 70., screen_height() / 2., 40., WHITE);
+       self.menu.draw(Menu::below_center());
+       set_camera(&self.camera);
+   }
+
 }
 
 #[macroquad::main("MyGame")]
 async fn main() {
     let mut game = Game::new().await;
+    let mut input = Input::load().await;
+    let mut clock = FixedClock::new(FIXED_STEP, MAX_FRAME_TIME);
     loop {
+        if game.is_level_done() {
+            game.next_level().await;
+        }
 
-        clear_background(Color {
-            r: 93. / 255.,
-            g: 148. / 255.,
-            b: 252. / 255.,
-            a: 255. / 255.,
-        });
+        input.update();
+        let pause_pressed = input.is_pressed(Action::Pause);
+        let mut chosen = false;
 
-        let mut is_moving = false;
-        if is_key_down(KeyCode::Left) {
-            game.left();
-            is_moving = true;
-        }
+        if game.state == GameState::Paused && pause_pressed {
+            game.resume();
+        } else if game.has_menu() {
+            if let Some(action) = game.menu.update(&input) {
+                if !game.choose(action).await {
+                    break;
+                }
 
-        if is_key_down(KeyCode::Right) {
-            game.right();
-            is_moving = true;
+                chosen = true;
+            }
+        } else if game.state == GameState::Playing && pause_pressed {
+            game.pause();
         }
 
-        if is_key_pressed(KeyCode::Space) {
-            game.jump();
-        }
+        if !game.is_frozen() {
+            // A confirm can share a button with jump; don't let it do both.
+            if !chosen {
+                handle_movement(&mut game, &input);
+            }
 
-        if !is_moving {
-            game.stop();
+            for _ in 0..clock.advance(get_frame_time()) {
+                game.update(clock.step());
+            }
         }
 
-        game.draw();
+        game.draw(clock.alpha());
 
         next_frame().await
     }
 }
+
+fn handle_movement(game: &mut Game, input: &Input) {
+    let mut is_moving = false;
+    if input.is_down(Action::MoveLeft) {
+        game.left();
+        is_moving = true;
+    }
+
+    if input.is_down(Action::MoveRight) {
+        game.right();
+        is_moving = true;
+    }
+
+    if input.is_pressed(Action::Jump) {
+        game.jump();
+    }
+
+    if input.is_released(Action::Jump) {
+        game.release_jump();
+    }
+
+    if !is_moving {
+        game.stop();
+    }
+}
@@ -1,76 +1,399 @@
+use macroquad::color::{Color, WHITE};
 use macroquad::file::load_string;
-use macroquad::math::Rect;
-use macroquad::texture::load_texture;
-use macroquad_tiled::{Map, TilesIterator};
+use macroquad::math::{vec2, Rect, Vec2};
+use macroquad::texture::{draw_texture, load_texture, Texture2D};
+use macroquad_tiled::{Map, Tile, TilesIterator};
 use macroquad_tiled as tiled;
+use nanoserde::DeJson;
+use tiled_info::MapInfo;
 
-use crate::sprites::Player;
+use crate::{enemies::EnemyKind, levels::Level};
+
+const ENTITIES_LAYER: &str = "entities";
+const GROUND_LAYER: &str = "ground";
+
+/// Sky colour when the map doesn't set one.
+const DEFAULT_BACKGROUND: Color = Color {
+    r: 93. / 255.,
+    g: 148. / 255.,
+    b: 252. / 255.,
+    a: 1.,
+};
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum EntityKind {
+    Player,
+    Enemy(EnemyKind),
+    Coin,
+    Checkpoint,
+    Goal,
+}
+
+impl EntityKind {
+    pub fn from_name(name: &str) -> Option<EntityKind> {
+        match name {
+            "player" => Some(EntityKind::Player),
+            "coin" => Some(EntityKind::Coin),
+            "checkpoint" => Some(EntityKind::Checkpoint),
+            "goal" => Some(EntityKind::Goal),
+            _ => EnemyKind::from_name(name).map(EntityKind::Enemy),
+        }
+    }
+}
+
+/// What comes out of a question block.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Contents {
+    Coin,
+    Mushroom,
+}
+
+impl Contents {
+    pub fn from_name(name: &str) -> Contents {
+        match name {
+            "mushroom" => Contents::Mushroom,
+            _ => Contents::Coin,
+        }
+    }
+}
+
+/// How a ground tile behaves, from its type in the tileset.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum TileKind {
+    Solid,
+    Question(Contents),
+    Brick,
+    Coin,
+    Used,
+}
+
+/// Something placed on the `entities` object layer in Tiled.
+#[derive(Clone, Copy, Debug)]
+pub struct Entity {
+    pub kind: EntityKind,
+    pub area: Rect,
+}
+
+enum LayerKind {
+    Tiles,
+    Image(Texture2D),
+}
+
+/// A tile or image layer drawn behind the entities. Its parallax factor
+/// comes from the layer's "Parallax Factor" in Tiled: 1 scrolls with the
+/// ground, 0 stays put, in between scrolls slower.
+struct DrawLayer {
+    name: String,
+    kind: LayerKind,
+    offset: Vec2,
+    parallax: Vec2,
+    repeat_x: bool,
+}
 
 pub struct MapDrawer {
-    map: Map
+    map: Map,
+    layers: Vec<DrawLayer>,
+    background: Color,
+    entities: Vec<Entity>,
 }
 
 impl MapDrawer {
 
-    pub async fn new() -> Self {
-        let json = load_string("./assets/map.json")
+    pub async fn new(level: &Level) -> Self {
+        let json = load_string(&format!("./assets/{}", level.map))
             .await
             .unwrap();
 
-        let texture = load_texture("./assets/tileset.png")
+        let texture = load_texture(&format!("./assets/{}", level.tileset))
             .await
             .unwrap();
 
-        let param_texture = ( "tileset.png", texture);
+        let info: MapInfo = DeJson::deserialize_json(&json)
+            .unwrap();
+
+        let mut textures = vec![(level.tileset.clone(), texture)];
+
+        for image in info.layers.iter().filter_map(|l| l.image.as_ref()) {
+            let texture = load_texture(&format!("./assets/{}", image))
+                .await
+                .unwrap();
+
+            textures.push((image.clone(), texture));
+        }
+
+        let layers = info.layers
+            .iter()
+            .filter(|l| l.visible.unwrap_or(true))
+            .filter_map(|l| {
+                let kind = match l.ty.as_str() {
+                    "tilelayer" => LayerKind::Tiles,
+                    "imagelayer" => {
+                        let image = l.image.as_ref()?;
+                        let (_, texture) = textures.iter().find(|(name, _)| name == image)?;
+                        LayerKind::Image(texture.clone())
+                    }
+                    _ => return None,
+                };
+
+                Some(DrawLayer {
+                    name: l.name.clone(),
+                    kind,
+                    offset: vec2(l.offsetx.unwrap_or(0.), l.offsety.unwrap_or(0.)),
+                    parallax: vec2(l.parallaxx.unwrap_or(1.), l.parallaxy.unwrap_or(1.)),
+                    repeat_x: l.repeatx.unwrap_or(false),
+                })
+            })
+            .collect();
+
+        let background = info.backgroundcolor
+            .as_deref()
+            .and_then(parse_color)
+            .unwrap_or(DEFAULT_BACKGROUND);
+
+        let param_textures: Vec<(&str, Texture2D)> = textures
+            .iter()
+            .map(|(name, texture)| (name.as_str(), texture.clone()))
+            .collect();
+
+        // Tiled saves object layers as "objectgroup", macroquad_tiled only
+        // knows them as "objectlayer".
+        let json = json.replace("\"objectgroup\"", "\"objectlayer\"");
 
         let map = tiled::load_map(
             &json, 
-            &[param_texture], 
+            &param_textures, 
             &[])
             .unwrap();
 
+        let entities = find_entities(&map);
+
         MapDrawer {
-            map: map,
+            map,
+            layers,
+            background,
+            entities,
         }
 
     }
 
-    pub fn draw(&self) {
+    pub fn background(&self) -> Color {
+        self.background
+    }
+
+    /// Draws the layers in their Tiled order, each shifted by its parallax
+    /// against `view`, the part of the map on screen.
+    pub fn draw(&self, view: Rect) {
         let area = self.px_area();
-        self.map.draw_tiles("ground", area, None);
-        self.map.draw_tiles("background", area, None);
+
+        for layer in &self.layers {
+            let scroll = vec2(view.x, view.y) * (Vec2::ONE - layer.parallax);
+            let origin = scroll + layer.offset;
+
+            match &layer.kind {
+                LayerKind::Tiles => {
+                    let dest = Rect::new(origin.x, origin.y, area.w, area.h);
+                    self.map.draw_tiles(&layer.name, dest, None);
+                }
+                LayerKind::Image(texture) => {
+                    draw_image_layer(texture, origin, layer.repeat_x, view);
+                }
+            }
+        }
     }
  
     pub fn px_width(&self) -> f32{
         let tile = &self.map.raw_tiled_map;
-        return (tile.tilewidth * tile.width) as f32;
+        (tile.tilewidth * tile.width) as f32
     }
 
     pub fn px_height(&self) -> f32{
         let tile = &self.map.raw_tiled_map;
-        return (tile.tileheight * tile.height) as f32;
+        (tile.tileheight * tile.height) as f32
     }
 
     pub fn tile_height(&self) -> f32{
         let tile = &self.map.raw_tiled_map;
-        return tile.tileheight as f32; 
+        tile.tileheight as f32
     }
 
     pub fn tile_width(&self) -> f32{
         let tile = &self.map.raw_tiled_map;
-        return tile.tilewidth as f32; 
+        tile.tilewidth as f32
     }
 
     pub fn width(&self) -> f32{
         let tile = &self.map.raw_tiled_map;
-        return tile.width as f32; 
+        tile.width as f32
     }
 
     pub fn px_area(&self) -> Rect {
-        return Rect::new(0., 0., self.px_width(), self.px_height())
+        Rect::new(0., 0., self.px_width(), self.px_height())
+    }
+
+    pub fn get_tiles(&self) -> TilesIterator<'_> {
+        self.map.tiles(GROUND_LAYER, None)
+    }
+
+    pub fn tile_at(&self, x: u32, y: u32) -> Option<&Tile> {
+        let layer = &self.map.layers[GROUND_LAYER];
+
+        if x >= layer.width || y >= layer.height {
+            return None;
+        }
+
+        layer.data[(y * layer.width + x) as usize].as_ref()
+    }
+
+    pub fn kind_at(&self, x: u32, y: u32) -> Option<TileKind> {
+        self.tile_at(x, y).map(|t| self.tile_kind(t))
+    }
+
+    /// Reads the tile type and its `contents` property from the tileset.
+    pub fn tile_kind(&self, tile: &Tile) -> TileKind {
+        let Some((ty, properties)) = self.tile_info(&tile.tileset, tile.id) else {
+            return TileKind::Solid;
+        };
+
+        match ty {
+            "question" => {
+                let contents = properties
+                    .iter()
+                    .find(|(name, _)| *name == "contents")
+                    .map(|(_, value)| Contents::from_name(value))
+                    .unwrap_or(Contents::Coin);
+
+                TileKind::Question(contents)
+            }
+            "brick" => TileKind::Brick,
+            "coin" => TileKind::Coin,
+            "used" => TileKind::Used,
+            _ => TileKind::Solid,
+        }
+    }
+
+    /// Replaces a ground tile, `None` clears it. The new tile comes from
+    /// the same tileset as the old one.
+    pub fn set_tile(&mut self, x: u32, y: u32, id: Option<u32>) {
+        let Some(tileset) = self.tile_at(x, y).map(|t| t.tileset.clone()) else {
+            return;
+        };
+
+        let tile = id.map(|id| Tile {
+            id,
+            attrs: self.tile_info(&tileset, id)
+                .map(|(ty, _)| ty.to_string())
+                .unwrap_or_default(),
+            tileset,
+        });
+
+        let layer = self.map.layers.get_mut(GROUND_LAYER).unwrap();
+        layer.data[(y * layer.width + x) as usize] = tile;
+    }
+
+    /// The first tile in a tileset of the given kind, like the used block
+    /// a question block turns into.
+    pub fn find_tile(&self, kind: TileKind) -> Option<u32> {
+        for tileset in &self.map.raw_tiled_map.tilesets {
+            for info in &tileset.tiles {
+                let tile = Tile {
+                    id: info.id as u32,
+                    tileset: tileset.name.clone(),
+                    attrs: String::new(),
+                };
+
+                if self.tile_kind(&tile) == kind {
+                    return Some(tile.id);
+                }
+            }
+        }
+
+        None
+    }
+
+    /// Type and properties (name, value) of a tile, if the tileset says
+    /// anything about it.
+    fn tile_info(&self, tileset: &str, id: u32) -> Option<(&str, Vec<(&str, &str)>)> {
+        let info = self.map.raw_tiled_map.tilesets
+            .iter()
+            .find(|t| t.name == tileset)?
+            .tiles
+            .iter()
+            .find(|t| t.id == id as usize)?;
+
+        let properties = info.properties
+            .iter()
+            .map(|p| (p.name.as_str(), p.value.as_str()))
+            .collect();
+
+        Some((info.ty.as_deref().unwrap_or_default(), properties))
     }
 
-    pub fn get_tiles(&self) -> TilesIterator {
-        self.map.tiles("ground", None)
+    pub fn entities(&self) -> &[Entity] {
+        &self.entities
     }
+
+    pub fn entities_of(&self, kind: EntityKind) -> impl Iterator<Item = &Entity> {
+        self.entities
+            .iter()
+            .filter(move |e| e.kind == kind)
+    }
+}
+
+/// Entities from the object layer. The object class (or its name when it
+/// has no class) says what it is.
+fn find_entities(map: &Map) -> Vec<Entity> {
+    let mut entities = vec![];
+
+    let layers = map.raw_tiled_map.layers
+        .iter()
+        .filter(|l| l.name == ENTITIES_LAYER);
+
+    for object in layers.flat_map(|l| &l.objects) {
+        let name = if object.ty.is_empty() { &object.name } else { &object.ty };
+
+        let Some(kind) = EntityKind::from_name(name) else {
+            println!("Unknown entity '{}' in map", name);
+            continue;
+        };
+
+        // Tile objects are anchored at their bottom left corner.
+        let y = if object.gid.is_some() { object.y - object.height } else { object.y };
+
+        entities.push(Entity {
+            kind,
+            area: Rect::new(object.x, y, object.width, object.height),
+        });
+    }
+
+    entities
+}
+
+/// Draws an image layer, tiled across `view` when it repeats.
+fn draw_image_layer(texture: &Texture2D, origin: Vec2, repeat_x: bool, view: Rect) {
+    if !repeat_x {
+        draw_texture(texture, origin.x, origin.y, WHITE);
+        return;
+    }
+
+    let width = texture.width();
+    let mut x = origin.x + ((view.x - origin.x) / width).floor() * width;
+
+    while x < view.right() {
+        draw_texture(texture, x, origin.y, WHITE);
+        x += width;
+    }
+}
+
+/// Tiled colours: `#rrggbb` or `#aarrggbb`.
+fn parse_color(text: &str) -> Option<Color> {
+    let hex = text.strip_prefix('#')?;
+    let value = u32::from_str_radix(hex, 16).ok()?;
+
+    let (a, rgb) = match hex.len() {
+        6 => (0xff, value),
+        8 => (value >> 24, value & 0xffffff),
+        _ => return None,
+    };
+
+    Some(Color::from_rgba((rgb >> 16) as u8, (rgb >> 8) as u8, rgb as u8, a as u8))
 }
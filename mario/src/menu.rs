@@ -0,0 +1,88 @@
+use macroquad::{
+    color::{WHITE, YELLOW},
+    text::{draw_text, measure_text},
+    window::{screen_height, screen_width},
+};
+
+use crate::input::{Action, Input};
+
+const FONT_SIZE: f32 = 30.;
+const LINE_HEIGHT: f32 = 40.;
+
+/// What picking a menu entry asks the game to do.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum MenuAction {
+    Start,
+    Resume,
+    Restart,
+    Title,
+    Quit,
+}
+
+/// A vertical list of entries picked with the up, down and confirm actions.
+pub struct Menu {
+    items: Vec<(&'static str, MenuAction)>,
+    selected: usize,
+}
+
+impl Menu {
+    pub fn new(items: Vec<(&'static str, MenuAction)>) -> Self {
+        Menu { items, selected: 0 }
+    }
+
+    pub fn title() -> Self {
+        Menu::new(vec![("START", MenuAction::Start), ("QUIT", MenuAction::Quit)])
+    }
+
+    pub fn paused() -> Self {
+        Menu::new(vec![
+            ("RESUME", MenuAction::Resume),
+            ("RESTART", MenuAction::Restart),
+            ("QUIT TO TITLE", MenuAction::Title),
+        ])
+    }
+
+    pub fn game_over() -> Self {
+        Menu::new(vec![
+            ("PLAY AGAIN", MenuAction::Restart),
+            ("QUIT TO TITLE", MenuAction::Title),
+        ])
+    }
+
+    /// Moves the selection and returns the entry picked this frame, if any.
+    pub fn update(&mut self, input: &Input) -> Option<MenuAction> {
+        let count = self.items.len();
+
+        if input.is_pressed(Action::MenuUp) {
+            self.selected = (self.selected + count - 1) % count;
+        }
+
+        if input.is_pressed(Action::MenuDown) {
+            self.selected = (self.selected + 1) % count;
+        }
+
+        if input.is_pressed(Action::Confirm) {
+            return Some(self.items[self.selected].1);
+        }
+
+        None
+    }
+
+    /// Draws the entries centred on the screen, starting at `top`.
+    /// Expects screen space.
+    pub fn draw(&self, top: f32) {
+        for (i, (label, _)) in self.items.iter().enumerate() {
+            let selected = i == self.selected;
+            let text = if selected { format!("> {} <", label) } else { label.to_string() };
+            let color = if selected { YELLOW } else { WHITE };
+            let width = measure_text(&text, None, FONT_SIZE as u16, 1.).width;
+
+            draw_text(&text, (screen_width() - width) / 2., top + i as f32 * LINE_HEIGHT, FONT_SIZE, color);
+        }
+    }
+
+    /// Top of a menu drawn under a heading in the middle of the screen.
+    pub fn below_center() -> f32 {
+        screen_height() / 2. + 60.
+    }
+}
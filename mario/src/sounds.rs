@@ -1,40 +1,155 @@
-use macroquad::audio::{load_sound, play_sound, PlaySoundParams, Sound};
+use std::collections::HashMap;
 
+use macroquad::audio::{load_sound, play_sound, stop_sound, PlaySoundParams, Sound};
+use macroquad::file::load_string;
+
+const MANIFEST: &str = "./assets/sounds.txt";
+
+/// Music plays one track at a time and replaces whatever was on, effects
+/// play over each other.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Channel {
+    Music,
+    Effects,
+}
+
+struct Cue {
+    sound: Sound,
+    channel: Channel,
+    looped: bool,
+}
+
+/// Every sound listed in the manifest, by name.
 pub struct Sounds {
-    background: Sound,
-    jump: Sound
+    cues: HashMap<String, Cue>,
+    master: f32,
+    music: f32,
+    effects: f32,
+    playing: Option<String>,
 }
 
 impl Sounds {
 
     pub async fn new() -> Self {
-
-        let background = load_sound("./assets/background.ogg")
+        let manifest = load_string(MANIFEST)
             .await
             .unwrap();
 
-        let jump = load_sound("./assets/jump.wav")
-            .await
-            .unwrap();
+        let mut sounds = Sounds {
+            cues: HashMap::new(),
+            master: 1.,
+            music: 0.5,
+            effects: 0.5,
+            playing: None,
+        };
+
+        sounds.parse(&manifest).await;
+        sounds
+    }
+
+    /// `master|music|sfx volume` lines set a volume from 0 to 1,
+    /// `name music|sfx file loop|once` lines add a cue. Blank lines and
+    /// lines starting with `#` are skipped.
+    async fn parse(&mut self, manifest: &str) {
+        let lines = manifest
+            .lines()
+            .map(|line| line.trim())
+            .filter(|line| !line.is_empty() && !line.starts_with('#'));
+
+        for line in lines {
+            let parts: Vec<&str> = line.split_whitespace().collect();
+
+            match parts.as_slice() {
+                [setting, volume] => {
+                    let Ok(volume) = volume.parse::<f32>() else {
+                        println!("Skipping volume '{}' in {}", line, MANIFEST);
+                        continue;
+                    };
+
+                    let volume = volume.clamp(0., 1.);
+
+                    match *setting {
+                        "master" => self.master = volume,
+                        "music" => self.music = volume,
+                        "sfx" => self.effects = volume,
+                        _ => println!("Skipping volume '{}' in {}", line, MANIFEST),
+                    }
+                }
+                [name, channel, file, repeat] => {
+                    let channel = match *channel {
+                        "music" => Channel::Music,
+                        "sfx" => Channel::Effects,
+                        _ => {
+                            println!("Skipping cue '{}' in {}", line, MANIFEST);
+                            continue;
+                        }
+                    };
 
-        Sounds{
-            background,
-            jump
+                    let sound = load_sound(&format!("./assets/{}", file))
+                        .await
+                        .unwrap();
+
+                    self.cues.insert(name.to_string(), Cue {
+                        sound,
+                        channel,
+                        looped: *repeat == "loop",
+                    });
+                }
+                _ => println!("Skipping sound '{}' in {}", line, MANIFEST),
+            }
         }
     }
 
-    pub fn play_background(&self) {
-        play_sound(&self.background, PlaySoundParams {
-            volume: 0.5,
-            looped: true
-        });
+    fn volume(&self, channel: Channel) -> f32 {
+        let volume = match channel {
+            Channel::Music => self.music,
+            Channel::Effects => self.effects,
+        };
+
+        self.master * volume
     }
 
-    pub fn play_jump(&self) {
-        play_sound(&self.jump, PlaySoundParams {
-            volume: 0.5,
-            looped: false
+    /// Plays a cue. A music cue takes over from the track that was on.
+    pub fn play(&mut self, name: &str) {
+        let Some(cue) = self.cues.get(name) else {
+            println!("No sound '{}' in {}", name, MANIFEST);
+            return;
+        };
+
+        if cue.channel == Channel::Music {
+            self.stop_music();
+            self.playing = Some(name.to_string());
+        }
+
+        let cue = &self.cues[name];
+
+        play_sound(&cue.sound, PlaySoundParams {
+            volume: self.volume(cue.channel),
+            looped: cue.looped,
         });
     }
 
+    pub fn stop_music(&mut self) {
+        let Some(name) = self.playing.take() else {
+            return;
+        };
+
+        stop_sound(&self.cues[&name].sound);
+    }
+
+    /// Silences the track but remembers it for `resume_music`.
+    pub fn pause_music(&self) {
+        if let Some(name) = &self.playing {
+            stop_sound(&self.cues[name].sound);
+        }
+    }
+
+    /// Starts the paused track over, macroquad can't pick up where it
+    /// stopped.
+    pub fn resume_music(&mut self) {
+        if let Some(name) = self.playing.clone() {
+            self.play(&name);
+        }
+    }
+
 }
@@ -3,93 +3,278 @@ use std::{cell::RefCell, rc::Rc};
 use macroquad::{
     color::WHITE,
     math::{Rect, Vec2, vec2},
+    text::draw_text,
     texture::{DrawTextureParams, Texture2D, draw_texture_ex, load_texture},
-    time::{get_frame_time, get_time},
 };
 use macroquad_platformer::{Actor, World};
 
-use crate::consts::{
-    FRAME_EVERY, GRAVITY, JUMP, PLAYER_SIZE, PLAYER_SPEED, SPEED_ENEMY, TILE_GROUND, TILE_SIZE
+use crate::{
+    animation::{Animation, AnimationSet},
+    consts::{
+        BLINK_EVERY, COIN_POP_SPEED, COIN_POP_TIME, COYOTE_TIME, FRAME_EVERY, GRAVITY,
+        INVINCIBLE_TIME, JUMP, JUMP_BUFFER_TIME, JUMP_CUT, MUSHROOM_SPEED, PARKED_POS, PLAYER_SIZE,
+        PLAYER_SPEED, POPUP_SPEED, POPUP_TIME, STOMP_BOUNCE, TILE_GROUND, TILE_SIZE,
+    },
 };
 
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum PlayerState {
+    Idle,
+    Run,
+    Jump,
+    Fall,
+    Die,
+}
+
 pub struct Player {
     world: Rc<RefCell<World>>,
-    actor: Actor,
-    texture: Texture2D,
+    /// The world can't resize an actor, so there is one per size and the
+    /// one not in use is parked.
+    small_actor: Actor,
+    big_actor: Actor,
+    animations: AnimationSet<PlayerState>,
+    big_animations: AnimationSet<PlayerState>,
     speed: Vec2,
-    frame: usize,
-    frame_time: f32,
+    prev_pos: Vec2,
+    dead: bool,
+    big: bool,
+    bumped: bool,
+    jumped: bool,
+    invincible_time: f32,
+    coyote_time: f32,
+    jump_buffer: f32,
 }
 
 impl Player {
-    pub async fn new(world: Rc<RefCell<World>>) -> Self {
-        let texture = load_texture("./assets/sprite-running.png").await.unwrap();
+    pub async fn new(pos: Vec2, world: Rc<RefCell<World>>) -> Self {
+        let texture = load_texture("./assets/small-mario.png").await.unwrap();
+        let big_texture = load_texture("./assets/big-mario.png").await.unwrap();
 
-        let init_pos = vec2(10., TILE_GROUND * TILE_SIZE - PLAYER_SIZE);
+        let small_actor = world
+            .borrow_mut()
+            .add_actor(pos, PLAYER_SIZE as i32, PLAYER_SIZE as i32);
 
-        let actor = world
+        let big_actor = world
             .borrow_mut()
-            .add_actor(init_pos, PLAYER_SIZE as i32, PLAYER_SIZE as i32);
+            .add_actor(PARKED_POS, PLAYER_SIZE as i32, PLAYER_SIZE as i32 * 2);
 
         Player {
-            world: world,
-            actor: actor,
-            texture: texture,
+            world,
+            small_actor,
+            big_actor,
+            animations: Player::animations(texture),
+            big_animations: Player::big_animations(big_texture),
             speed: vec2(0., 0.),
-            frame: 0,
-            frame_time: 0.,
+            prev_pos: pos,
+            dead: false,
+            big: false,
+            bumped: false,
+            jumped: false,
+            invincible_time: 0.,
+            coyote_time: 0.,
+            jump_buffer: 0.,
         }
     }
 
-    pub fn draw(&mut self) {
-        let area = Rect::new(
-            self.frame as f32 * (PLAYER_SIZE + 2.),
-            0.,
-            PLAYER_SIZE,
-            PLAYER_SIZE,
-        );
+    /// `small-mario.png` holds idle, three run frames, skid, jump and die.
+    fn animations(texture: Texture2D) -> AnimationSet<PlayerState> {
+        let size = vec2(PLAYER_SIZE, PLAYER_SIZE);
+        let frame = |i: f32| Rect::new(i * PLAYER_SIZE, 0., PLAYER_SIZE, PLAYER_SIZE);
+
+        AnimationSet::new(texture, PlayerState::Idle)
+            .with(PlayerState::Idle, Animation::still(frame(0.)))
+            .with(PlayerState::Run, Animation::strip(vec2(PLAYER_SIZE, 0.), size, PLAYER_SIZE, 3, FRAME_EVERY))
+            .with(PlayerState::Jump, Animation::still(frame(5.)))
+            .with(PlayerState::Fall, Animation::still(frame(5.)))
+            .with(PlayerState::Die, Animation::still(frame(6.)))
+    }
 
-        let pos = self.pos();
+    /// `big-mario.png` has the same frames twice as tall, without a die
+    /// frame since the player shrinks before dying.
+    fn big_animations(texture: Texture2D) -> AnimationSet<PlayerState> {
+        let size = vec2(PLAYER_SIZE, PLAYER_SIZE * 2.);
+        let frame = |i: f32| Rect::new(i * PLAYER_SIZE, 0., PLAYER_SIZE, PLAYER_SIZE * 2.);
+
+        AnimationSet::new(texture, PlayerState::Idle)
+            .with(PlayerState::Idle, Animation::still(frame(0.)))
+            .with(PlayerState::Run, Animation::strip(vec2(PLAYER_SIZE, 0.), size, PLAYER_SIZE, 3, FRAME_EVERY))
+            .with(PlayerState::Jump, Animation::still(frame(5.)))
+            .with(PlayerState::Fall, Animation::still(frame(5.)))
+    }
 
-        draw_texture_ex(
-            &self.texture,
-            pos.x,
-            pos.y,
-            WHITE,
-            DrawTextureParams {
-                source: Some(area),
-                ..Default::default()
-            },
-        );
+    pub fn update(&mut self, dt: f32) {
+        self.prev_pos = self.pos();
+
+        if self.dead {
+            return;
+        }
 
-        self.mov();
-        self.mov_gravity();
-        self.change_frame();
+        self.invincible_time = (self.invincible_time - dt).max(0.);
+
+        self.update_jump(dt);
+        self.mov(dt);
+        self.mov_gravity(dt);
+        self.change_frame(dt);
     }
 
-    pub fn change_frame(&mut self) {
-        if self.speed.x == 0. {
-            self.frame = 0;
+    /// Draws between the last two steps, `alpha` of the way to the newest.
+    pub fn draw(&self, alpha: f32) {
+        // Blinks while it can't be hurt.
+        let hidden = self.invincible_time > 0.
+            && (self.invincible_time / BLINK_EVERY) as i32 % 2 == 0;
+
+        if hidden {
             return;
         }
 
-        if self.frame_time < FRAME_EVERY {
-            self.frame_time += get_frame_time();
+        let animations = if self.big { &self.big_animations } else { &self.animations };
+        animations.draw(self.draw_pos(alpha));
+    }
+
+    pub fn draw_pos(&self, alpha: f32) -> Vec2 {
+        self.prev_pos.lerp(self.pos(), alpha)
+    }
+
+    pub fn change_frame(&mut self, dt: f32) {
+        let state = self.state();
+        let animations = if self.big { &mut self.big_animations } else { &mut self.animations };
+
+        animations.set_state(state);
+        animations.update(dt);
+
+        if self.speed.x < 0. {
+            animations.flip_x = true;
+        }
+
+        if self.speed.x > 0. {
+            animations.flip_x = false;
+        }
+    }
+
+    pub fn state(&self) -> PlayerState {
+        if self.dead {
+            return PlayerState::Die;
+        }
+
+        if !self.is_on_ground() {
+            if self.speed.y < 0. {
+                return PlayerState::Jump;
+            }
+
+            return PlayerState::Fall;
+        }
+
+        if self.speed.x != 0. {
+            return PlayerState::Run;
+        }
+
+        PlayerState::Idle
+    }
+
+    pub fn die(&mut self) {
+        self.resize(false);
+        self.dead = true;
+        self.speed = vec2(0., 0.);
+        self.invincible_time = 0.;
+        self.animations.set_state(PlayerState::Die);
+    }
+
+    pub fn is_big(&self) -> bool {
+        self.big
+    }
+
+    pub fn is_invincible(&self) -> bool {
+        self.invincible_time > 0.
+    }
+
+    pub fn grow(&mut self) {
+        self.resize(true);
+    }
+
+    /// Drops back to small and can't be hurt for a moment.
+    pub fn shrink(&mut self) {
+        self.resize(false);
+        self.invincible_time = INVINCIBLE_TIME;
+    }
+
+    fn actor(&self) -> Actor {
+        if self.big {
+            return self.big_actor;
+        }
+
+        self.small_actor
+    }
+
+    /// Swaps to the other size's actor, standing on the same feet.
+    fn resize(&mut self, big: bool) {
+        if self.big == big {
             return;
         }
 
-        self.frame_time = 0.;
-        self.frame += 1;
+        let pos = self.pos();
+        let old_actor = self.actor();
+        let old_height = self.height();
+        let flip_x = if self.big { self.big_animations.flip_x } else { self.animations.flip_x };
+
+        self.big = big;
+        self.animations.flip_x = flip_x;
+        self.big_animations.flip_x = flip_x;
+
+        let feet = vec2(pos.x, pos.y + old_height - self.height());
 
-        if self.frame >= 4 {
-            self.frame = 0;
+        let mut world = self.world.borrow_mut();
+        world.set_actor_position(old_actor, PARKED_POS);
+        world.set_actor_position(self.actor(), feet);
+        drop(world);
+
+        self.prev_pos = feet;
+    }
+
+    pub fn height(&self) -> f32 {
+        if self.big {
+            return PLAYER_SIZE * 2.;
         }
+
+        PLAYER_SIZE
+    }
+
+    /// Whether the head hit a ceiling since the last call.
+    pub fn take_bump(&mut self) -> bool {
+        std::mem::take(&mut self.bumped)
+    }
+
+    /// Where the player starts when the map has no player entity.
+    pub fn start_pos() -> Vec2 {
+        vec2(10., TILE_GROUND * TILE_SIZE - PLAYER_SIZE)
     }
 
     pub fn pos(&self) -> Vec2 {
         let world = self.world.borrow();
 
-        world.actor_pos(self.actor)
+        world.actor_pos(self.actor())
+    }
+
+    pub fn area(&self) -> Rect {
+        let pos = self.pos();
+        Rect {
+            x: pos.x,
+            y: pos.y,
+            w: PLAYER_SIZE,
+            h: self.height(),
+        }
+    }
+
+    pub fn respawn(&mut self, pos: Vec2) {
+        self.world
+            .borrow_mut()
+            .set_actor_position(self.actor(), pos);
+
+        self.speed = vec2(0., 0.);
+        self.prev_pos = pos;
+        self.jump_buffer = 0.;
+        self.dead = false;
+        self.animations.flip_x = false;
+        self.animations.set_state(PlayerState::Idle);
     }
 
     pub fn right(&mut self) {
@@ -104,37 +289,80 @@ impl Player {
         self.speed.x = 0.;
     }
 
+    /// Asks for a jump. It happens as soon as the player is on the ground,
+    /// or just left it, within the buffer time.
     pub fn jump(&mut self) {
-        if self.speed.y == 0. {
+        self.jump_buffer = JUMP_BUFFER_TIME;
+    }
+
+    pub fn is_falling(&self) -> bool {
+        self.speed.y > 0.
+    }
+
+    /// Pops up off an enemy's head.
+    pub fn bounce(&mut self) {
+        self.speed.y = -STOMP_BOUNCE;
+    }
+
+    /// Letting go of jump while still going up cuts the jump short.
+    pub fn release_jump(&mut self) {
+        if self.speed.y < 0. {
+            self.speed.y *= JUMP_CUT;
+        }
+    }
+
+    /// Whether a jump started since the last call.
+    pub fn take_jumped(&mut self) -> bool {
+        std::mem::take(&mut self.jumped)
+    }
+
+    fn update_jump(&mut self, dt: f32) {
+        if self.is_on_ground() && self.speed.y >= 0. {
+            self.coyote_time = COYOTE_TIME;
+        } else {
+            self.coyote_time = (self.coyote_time - dt).max(0.);
+        }
+
+        if self.jump_buffer > 0. && self.coyote_time > 0. {
             self.speed.y = -JUMP;
+            self.jump_buffer = 0.;
+            self.coyote_time = 0.;
+            self.jumped = true;
         }
+
+        self.jump_buffer = (self.jump_buffer - dt).max(0.);
     }
 
-    pub fn mov(&mut self) {
-        let dx = self.speed.x * get_frame_time();
-        let dy = self.speed.y * get_frame_time();
+    pub fn mov(&mut self, dt: f32) {
+        let dx = self.speed.x * dt;
+        let dy = self.speed.y * dt;
 
         let mut world = self.world.borrow_mut();
 
-        world.move_v(self.actor, dy);
-        world.move_h(self.actor, dx);
+        if !world.move_v(self.actor(), dy) && dy < 0. {
+            self.speed.y = 0.;
+            self.bumped = true;
+        }
+
+        world.move_h(self.actor(), dx);
     }
 
-    pub fn mov_gravity(&mut self) {
-        if self.is_on_ground() {
+    pub fn mov_gravity(&mut self, dt: f32) {
+        if self.is_on_ground() && self.speed.y >= 0. {
             self.speed.y = 0.;
             return;
         }
 
-        self.speed.y += GRAVITY * get_frame_time();
+        self.speed.y += GRAVITY * dt;
     }
 
+    /// Checks the whole width of the actor one pixel down, so standing on
+    /// the edge of a ledge still counts.
     pub fn is_on_ground(&self) -> bool {
         let world = self.world.borrow();
         let pos = self.pos();
-        let bottom = vec2(pos.x, pos.y + PLAYER_SIZE + 1.);
 
-        world.solid_at(bottom)
+        world.collide_check(self.actor(), pos + vec2(0., 1.))
     }
 
     pub fn bottom_center(&self) -> Vec2 {
@@ -142,132 +370,231 @@ impl Player {
 
         Vec2 {
             x: pos.x + PLAYER_SIZE / 2.,
-            y: pos.y + PLAYER_SIZE
+            y: pos.y + self.height()
         }
     }
 }
 
-pub struct Enemy {
-    world: Rc<RefCell<World>>,
-    actor: Actor,
+pub struct Coin {
     texture: Texture2D,
-    speed: Vec2,
+    animation: Animation,
+    pos: Vec2,
+    time: f32,
 }
 
-impl Enemy {
-    
-    pub async fn new(pos: Vec2, world: Rc<RefCell<World>>) -> Self {
-        let texture = load_texture("./assets/enemies.png")
-            .await
-            .unwrap();
+impl Coin {
+    pub fn new(pos: Vec2, texture: Texture2D) -> Self {
+        // Spins through the wide, half and edge frames and back.
+        let frames = [0., 1., 2., 1.]
+            .iter()
+            .map(|i| Rect::new(i * TILE_SIZE, 0., TILE_SIZE, TILE_SIZE))
+            .collect();
+
+        Coin {
+            texture,
+            animation: Animation::new(frames, FRAME_EVERY * 1.5, true),
+            pos,
+            time: 0.,
+        }
+    }
 
+    pub fn update(&mut self, dt: f32) {
+        self.time += dt;
+    }
 
-        let actor = world
-            .borrow_mut()
-            .add_actor(pos, PLAYER_SIZE as i32, PLAYER_SIZE as i32);
+    pub fn draw(&self) {
+        draw_texture_ex(
+            &self.texture,
+            self.pos.x,
+            self.pos.y,
+            WHITE,
+            DrawTextureParams {
+                source: Some(self.animation.frame(self.time)),
+                ..Default::default()
+            },
+        );
+    }
 
-        Enemy {
-            world: world,
-            actor: actor,
-            texture: texture,
-            speed: vec2(-SPEED_ENEMY, 0.)
-        }
+    pub fn area(&self) -> Rect {
+        Rect::new(self.pos.x + 3., self.pos.y + 1., TILE_SIZE - 6., TILE_SIZE - 2.)
     }
+}
 
+/// A coin knocked out of a question block. It jumps up and vanishes.
+pub struct PoppedCoin {
+    texture: Texture2D,
+    animation: Animation,
+    pos: Vec2,
+    prev_pos: Vec2,
+    time: f32,
+}
 
-    pub fn mov(&mut self) {
-        let dx = self.speed.x * get_frame_time();
-        let dy = self.speed.y * get_frame_time();
+impl PoppedCoin {
+    pub fn new(pos: Vec2, texture: Texture2D) -> Self {
+        let frames = [0., 1., 2., 1.]
+            .iter()
+            .map(|i| Rect::new(i * TILE_SIZE, 0., TILE_SIZE, TILE_SIZE))
+            .collect();
+
+        PoppedCoin {
+            texture,
+            animation: Animation::new(frames, FRAME_EVERY / 2., true),
+            pos,
+            prev_pos: pos,
+            time: 0.,
+        }
+    }
 
-        let mut world = self.world.borrow_mut();
+    pub fn update(&mut self, dt: f32) {
+        self.prev_pos = self.pos;
+        self.time += dt;
+        self.pos.y -= COIN_POP_SPEED * dt;
+    }
 
-        world.move_v(self.actor, dy);
-        world.move_h(self.actor, dx);
+    pub fn draw(&self, alpha: f32) {
+        let pos = self.prev_pos.lerp(self.pos, alpha);
 
-        
+        draw_texture_ex(
+            &self.texture,
+            pos.x,
+            pos.y,
+            WHITE,
+            DrawTextureParams {
+                source: Some(self.animation.frame(self.time)),
+                ..Default::default()
+            },
+        );
     }
 
-    pub fn mov_gravity(&mut self) {
-        if self.is_on_ground() {
-            self.speed.y = 0.;
-            return;
-        }
-
-        self.speed.y += GRAVITY * get_frame_time();
+    pub fn is_gone(&self) -> bool {
+        self.time >= COIN_POP_TIME
     }
+}
 
-    pub fn is_on_ground(&self) -> bool {
-        let world = self.world.borrow();
-        let pos = self.pos();
-        let bottom = vec2(pos.x, pos.y + PLAYER_SIZE + 1.);
+/// Slides along the ground and turns at walls. Grows the player.
+pub struct Mushroom {
+    world: Rc<RefCell<World>>,
+    actor: Actor,
+    texture: Texture2D,
+    speed: Vec2,
+    prev_pos: Vec2,
+}
 
-        world.solid_at(bottom)
+impl Mushroom {
+    /// Takes over a parked actor from `spare` when there is one.
+    pub fn new(pos: Vec2, texture: Texture2D, world: Rc<RefCell<World>>, spare: Option<Actor>) -> Self {
+        let actor = match spare {
+            Some(actor) => {
+                world.borrow_mut().set_actor_position(actor, pos);
+                actor
+            }
+            None => world
+                .borrow_mut()
+                .add_actor(pos, TILE_SIZE as i32, TILE_SIZE as i32),
+        };
+
+        Mushroom {
+            world,
+            actor,
+            texture,
+            speed: vec2(MUSHROOM_SPEED, 0.),
+            prev_pos: pos,
+        }
     }
 
+    pub fn update(&mut self, dt: f32) {
+        self.prev_pos = self.pos();
+        self.mov(dt);
+    }
 
-    pub fn draw(&mut self) {
-        let area = Rect::new(
-            0.,
-            0.,
-            PLAYER_SIZE,
-            PLAYER_SIZE,
-        );
-
-        let pos = self.pos();
+    pub fn draw(&self, alpha: f32) {
+        let pos = self.prev_pos.lerp(self.pos(), alpha);
 
+        // The mushroom is the fourth frame in `items.png`.
         draw_texture_ex(
             &self.texture,
             pos.x,
             pos.y,
             WHITE,
             DrawTextureParams {
-                source: Some(area),
+                source: Some(Rect::new(TILE_SIZE * 3., 0., TILE_SIZE, TILE_SIZE)),
                 ..Default::default()
             },
         );
+    }
+
+    pub fn mov(&mut self, dt: f32) {
+        let dx = self.speed.x * dt;
+        let dy = self.speed.y * dt;
 
-        self.mov();
-        self.mov_gravity();
+        let mut world = self.world.borrow_mut();
 
+        world.move_v(self.actor, dy);
 
-        if self.h_collition() {
+        if !world.move_h(self.actor, dx) {
             self.speed.x *= -1.;
         }
 
+        let pos = world.actor_pos(self.actor);
+        let bottom = vec2(pos.x, pos.y + TILE_SIZE + 1.);
+
+        if world.solid_at(bottom) {
+            self.speed.y = 0.;
+        } else {
+            self.speed.y += GRAVITY * dt;
+        }
     }
 
     pub fn pos(&self) -> Vec2 {
-        let world = self.world.borrow();
-
-        world.actor_pos(self.actor)
+        self.world.borrow().actor_pos(self.actor)
     }
 
     pub fn area(&self) -> Rect {
         let pos = self.pos();
-        Rect {
-            x: pos.x,
-            y: pos.y,
-            w: PLAYER_SIZE,
-            h: PLAYER_SIZE,
-        }
+        Rect::new(pos.x, pos.y, TILE_SIZE, TILE_SIZE)
     }
 
-    pub fn h_collition(&self)-> bool {
-        let world = self.world.borrow();
-        let pos = self .pos();
-        let right = pos + vec2(1. + PLAYER_SIZE , 0.);
-        let left = pos + vec2(-1. , 0.);
+    /// Takes the mushroom out of play, handing back its actor for the
+    /// next one.
+    pub fn park(self) -> Actor {
+        self.world
+            .borrow_mut()
+            .set_actor_position(self.actor, PARKED_POS);
 
-        if world.solid_at(right) {
-            return true;
-        }
+        self.actor
+    }
+}
 
-        if world.solid_at(left) {
-            return true;
+/// Points floating up from where they were scored.
+pub struct ScorePopup {
+    points: u32,
+    pos: Vec2,
+    prev_pos: Vec2,
+    time: f32,
+}
+
+impl ScorePopup {
+    pub fn new(pos: Vec2, points: u32) -> Self {
+        ScorePopup {
+            points,
+            pos,
+            prev_pos: pos,
+            time: 0.,
         }
+    }
 
-        return false;
+    pub fn update(&mut self, dt: f32) {
+        self.prev_pos = self.pos;
+        self.time += dt;
+        self.pos.y -= POPUP_SPEED * dt;
+    }
 
+    pub fn draw(&self, alpha: f32) {
+        let pos = self.prev_pos.lerp(self.pos, alpha);
+        draw_text(&self.points.to_string(), pos.x, pos.y, 10., WHITE);
     }
 
+    pub fn is_gone(&self) -> bool {
+        self.time >= POPUP_TIME
+    }
 }
@@ -0,0 +1,11 @@
+/// Which screen the game is on. `Title` and `Paused` hold the world still;
+/// in the others it keeps running, so enemies walk on behind the game over
+/// and level complete screens.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum GameState {
+    Title,
+    Playing,
+    Paused,
+    LevelComplete,
+    GameOver,
+}
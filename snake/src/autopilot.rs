@@ -0,0 +1,388 @@
+use std::collections::VecDeque;
+
+use crate::state::{Direction, SnakeState, Square, WallMode};
+
+const DIRECTIONS: [Direction; 4] = [
+    Direction::Up,
+    Direction::Down,
+    Direction::Left,
+    Direction::Right,
+];
+
+/// Picks the next turn for the snake.
+///
+/// Takes the shortest path to the food when its first move is safe, and
+/// otherwise falls back to a Hamiltonian cycle over the board. Boards with
+/// no cycle (both sides odd) fall back to the move that leaves the most room.
+pub fn next_direction(state: &SnakeState) -> Option<Direction> {
+    if state.is_finished() {
+        return None;
+    }
+
+    let cycle = Cycle::new(state.width, state.height);
+
+    if let Some(path) = find_path(state, &state.snake, state.food)
+        && is_safe(state, cycle.as_ref(), &path)
+    {
+        return direction_to(state, state.head(), path[0]);
+    }
+
+    if let Some(cycle) = &cycle {
+        return cycle.shortcut(state);
+    }
+
+    DIRECTIONS
+        .into_iter()
+        .filter(|dir| is_free(state, &state.snake, step(state, state.head(), *dir)))
+        .max_by_key(|dir| {
+            let next = step(state, state.head(), *dir).unwrap();
+            room(state, &state.snake, next)
+        })
+}
+
+/// A closed path through every cell of the board.
+///
+/// While the snake only moves forward along the cycle, or jumps ahead
+/// without passing its own tail, its body stays in cycle order and the
+/// cells ahead of the head are always free.
+struct Cycle {
+    width: i32,
+    order: Vec<usize>,
+}
+
+impl Cycle {
+    fn new(width: i32, height: i32) -> Option<Cycle> {
+        let mut cycle = Cycle {
+            width,
+            order: vec![0; (width * height) as usize],
+        };
+
+        let mut at = Square::new(0, 0);
+
+        for i in 0..cycle.order.len() {
+            let index = cycle.index(at);
+            cycle.order[index] = i;
+
+            let (dx, dy) = cycle_direction(at, width, height)?.delta();
+            at = Square::new(at.x + dx, at.y + dy);
+        }
+
+        Some(cycle)
+    }
+
+    fn index(&self, square: Square) -> usize {
+        (square.y * self.width + square.x) as usize
+    }
+
+    /// Steps forward along the cycle from `from` to `to`.
+    fn distance(&self, from: Square, to: Square) -> usize {
+        let len = self.order.len();
+
+        (self.order[self.index(to)] + len - self.order[self.index(from)]) % len
+    }
+
+    /// How far ahead along the cycle the head may jump this tick.
+    fn max_jump(&self, state: &SnakeState) -> usize {
+        let len = self.order.len();
+        let head = state.head();
+        let tail = *state.snake.last().unwrap();
+
+        // Stop cutting corners once the board is half full.
+        if state.snake.len() * 2 > len {
+            return 1;
+        }
+
+        let to_tail = match self.distance(head, tail) {
+            0 => len,
+            d => d,
+        };
+
+        // Leave room for the snake to grow while it gets to the food.
+        to_tail.saturating_sub(4).max(1)
+    }
+
+    /// Moving to `to` must go forward along the cycle, not past the tail
+    /// and not past the food, so every tick gets the snake closer to eating.
+    fn allows(&self, state: &SnakeState, to: Square) -> bool {
+        let head = state.head();
+        let jump = self.distance(head, to);
+
+        jump > 0 && jump <= self.max_jump(state) && jump <= self.distance(head, state.food)
+    }
+
+    /// The allowed move that jumps furthest ahead along the cycle.
+    fn shortcut(&self, state: &SnakeState) -> Option<Direction> {
+        DIRECTIONS
+            .into_iter()
+            .filter_map(|dir| {
+                let next = step(state, state.head(), dir)?;
+
+                if is_free(state, &state.snake, Some(next)) && self.allows(state, next) {
+                    return Some((dir, self.distance(state.head(), next)));
+                }
+
+                None
+            })
+            .max_by_key(|(_, jump)| *jump)
+            .map(|(dir, _)| dir)
+    }
+}
+
+/// Shortest path from the head to `target`, excluding the head itself.
+fn find_path(state: &SnakeState, snake: &[Square], target: Square) -> Option<Vec<Square>> {
+    let width = state.width as usize;
+    let index = |s: Square| s.y as usize * width + s.x as usize;
+
+    let mut previous: Vec<Option<Square>> = vec![None; width * state.height as usize];
+    let mut seen = vec![false; previous.len()];
+    let mut queue = VecDeque::new();
+
+    let head = snake[0];
+    seen[index(head)] = true;
+    queue.push_back(head);
+
+    while let Some(current) = queue.pop_front() {
+        if current == target {
+            let mut path = vec![current];
+            let mut at = current;
+
+            while let Some(prev) = previous[index(at)] {
+                if prev == head {
+                    break;
+                }
+                path.push(prev);
+                at = prev;
+            }
+
+            path.reverse();
+            return Some(path);
+        }
+
+        for dir in DIRECTIONS {
+            let Some(next) = step(state, current, dir) else {
+                continue;
+            };
+
+            if seen[index(next)] || (next != target && !is_free(state, snake, Some(next))) {
+                continue;
+            }
+
+            seen[index(next)] = true;
+            previous[index(next)] = Some(current);
+            queue.push_back(next);
+        }
+    }
+
+    None
+}
+
+/// Whether taking the first step of `path` keeps the snake out of trouble.
+///
+/// With a cycle, the step must not jump past the tail along it. Without
+/// one, the snake follows `path` on a copy of itself and must still be able
+/// to reach its tail after eating.
+fn is_safe(state: &SnakeState, cycle: Option<&Cycle>, path: &[Square]) -> bool {
+    if let Some(cycle) = cycle {
+        return cycle.allows(state, path[0]);
+    }
+
+    let mut snake = state.snake.clone();
+
+    for (i, square) in path.iter().enumerate() {
+        snake.insert(0, *square);
+
+        if i + 1 < path.len() {
+            snake.pop();
+        }
+    }
+
+    if snake.len() >= (state.width * state.height) as usize {
+        return true;
+    }
+
+    let tail = *snake.last().unwrap();
+    find_path(state, &snake, tail).is_some()
+}
+
+/// Direction along a Hamiltonian cycle that visits every cell once, if
+/// the board has one.
+fn cycle_direction(at: Square, w: i32, h: i32) -> Option<Direction> {
+    if w < 2 || h < 2 {
+        return None;
+    }
+
+    if h % 2 == 0 {
+        return Some(cycle_step(at.x, at.y, w, h));
+    }
+
+    if w % 2 == 0 {
+        let dir = cycle_step(at.y, at.x, h, w);
+
+        return Some(match dir {
+            Direction::Up => Direction::Left,
+            Direction::Down => Direction::Right,
+            Direction::Left => Direction::Up,
+            Direction::Right => Direction::Down,
+        });
+    }
+
+    None
+}
+
+/// Snakes right and left through columns 1.. and comes back up column 0.
+/// Needs an even `h`.
+fn cycle_step(x: i32, y: i32, w: i32, h: i32) -> Direction {
+    if x == 0 {
+        return if y == 0 { Direction::Right } else { Direction::Up };
+    }
+
+    if y % 2 == 0 {
+        return if x < w - 1 { Direction::Right } else { Direction::Down };
+    }
+
+    if x > 1 || y == h - 1 {
+        return Direction::Left;
+    }
+
+    Direction::Down
+}
+
+fn step(state: &SnakeState, from: Square, dir: Direction) -> Option<Square> {
+    let (dx, dy) = dir.delta();
+    let x = from.x + dx;
+    let y = from.y + dy;
+
+    if state.is_inside(x, y) {
+        return Some(Square::new(x, y));
+    }
+
+    match state.mode {
+        WallMode::Kill => None,
+        WallMode::Wrap => Some(Square::new(
+            x.rem_euclid(state.width),
+            y.rem_euclid(state.height),
+        )),
+    }
+}
+
+/// Whether the head can move to `square` next tick. The tail square is
+/// free because the tail moves away at the same time.
+fn is_free(state: &SnakeState, snake: &[Square], square: Option<Square>) -> bool {
+    let Some(square) = square else {
+        return false;
+    };
+
+    let body = &snake[..snake.len() - 1];
+
+    if state.snake.len() > 1 && square == snake[1] {
+        return false;
+    }
+
+    !body.contains(&square)
+}
+
+fn direction_to(state: &SnakeState, from: Square, to: Square) -> Option<Direction> {
+    DIRECTIONS
+        .into_iter()
+        .find(|dir| step(state, from, *dir) == Some(to))
+}
+
+/// Number of cells reachable from `from`.
+fn room(state: &SnakeState, snake: &[Square], from: Square) -> usize {
+    let width = state.width as usize;
+    let index = |s: Square| s.y as usize * width + s.x as usize;
+
+    let mut seen = vec![false; width * state.height as usize];
+    let mut queue = VecDeque::from([from]);
+    seen[index(from)] = true;
+    let mut count = 0;
+
+    while let Some(current) = queue.pop_front() {
+        count += 1;
+
+        for dir in DIRECTIONS {
+            let next = step(state, current, dir);
+
+            if let Some(next) = next
+                && !seen[index(next)]
+                && is_free(state, snake, Some(next))
+            {
+                seen[index(next)] = true;
+                queue.push_back(next);
+            }
+        }
+    }
+
+    count
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn play(width: i32, height: i32, mode: WallMode, seed: u64) -> SnakeState {
+        let mut state = SnakeState::new(width, height, mode, seed);
+
+        for _ in 0..100_000 {
+            if state.is_finished() {
+                break;
+            }
+
+            let dir = next_direction(&state);
+            state.step(dir);
+        }
+
+        state
+    }
+
+    #[test]
+    fn goes_straight_for_the_food() {
+        let mut state = SnakeState::new(10, 10, WallMode::Kill, 1);
+        state.snake = vec![Square::new(2, 2)];
+        state.food = Square::new(2, 6);
+
+        assert_eq!(next_direction(&state), Some(Direction::Down));
+    }
+
+    #[test]
+    fn cycle_visits_every_cell() {
+        for (w, h) in [(4, 4), (5, 4), (4, 5), (6, 2)] {
+            let mut at = Square::new(0, 0);
+            let mut seen = vec![];
+
+            for _ in 0..w * h {
+                seen.push(at);
+                let (dx, dy) = cycle_direction(at, w, h).unwrap().delta();
+                at = Square::new(at.x + dx, at.y + dy);
+                assert!(at.x >= 0 && at.y >= 0 && at.x < w && at.y < h);
+            }
+
+            assert_eq!(at, Square::new(0, 0));
+            seen.sort_by_key(|s| (s.y, s.x));
+            seen.dedup();
+            assert_eq!(seen.len(), (w * h) as usize);
+        }
+    }
+
+    #[test]
+    fn no_cycle_on_odd_boards() {
+        assert!(Cycle::new(5, 5).is_none());
+        assert!(Cycle::new(5, 4).is_some());
+    }
+
+    #[test]
+    fn wins_small_boards() {
+        for seed in 0..10 {
+            let state = play(6, 6, WallMode::Kill, seed);
+
+            assert!(state.won, "seed {} lost with {} squares", seed, state.snake.len());
+        }
+    }
+
+    #[test]
+    fn survives_with_wrapping_walls() {
+        let state = play(8, 6, WallMode::Wrap, 3);
+
+        assert!(!state.game_over);
+    }
+}
@@ -0,0 +1,114 @@
+use std::collections::VecDeque;
+
+use crate::state::{Direction, SnakeState};
+
+const MAX_TURNS: usize = 3;
+
+/// Turns pressed between ticks, handed to the snake one per tick.
+pub struct InputQueue {
+    turns: VecDeque<Direction>,
+}
+
+impl InputQueue {
+    pub fn new() -> InputQueue {
+        InputQueue {
+            turns: VecDeque::new(),
+        }
+    }
+
+    /// Queues a turn unless it repeats the last planned direction or
+    /// reverses into the snake's neck.
+    pub fn push(&mut self, dir: Direction, state: &SnakeState) {
+        if self.turns.len() >= MAX_TURNS {
+            return;
+        }
+
+        let last = self.turns.back().copied().unwrap_or(state.dir);
+
+        if dir == last {
+            return;
+        }
+
+        if state.snake.len() > 1 && dir == last.opposite() {
+            return;
+        }
+
+        self.turns.push_back(dir);
+    }
+
+    pub fn next(&mut self) -> Option<Direction> {
+        self.turns.pop_front()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::{Square, WallMode};
+
+    fn long_snake() -> SnakeState {
+        let mut state = SnakeState::new(10, 10, WallMode::Kill, 1);
+        state.snake = vec![Square::new(3, 3), Square::new(2, 3)];
+        state.dir = Direction::Right;
+        state
+    }
+
+    #[test]
+    fn one_turn_per_tick() {
+        let state = long_snake();
+        let mut input = InputQueue::new();
+
+        input.push(Direction::Up, &state);
+        input.push(Direction::Left, &state);
+
+        assert_eq!(input.next(), Some(Direction::Up));
+        assert_eq!(input.next(), Some(Direction::Left));
+        assert_eq!(input.next(), None);
+    }
+
+    #[test]
+    fn quick_double_turn_does_not_hit_the_neck() {
+        let mut state = long_snake();
+        let mut input = InputQueue::new();
+
+        input.push(Direction::Up, &state);
+        input.push(Direction::Left, &state);
+
+        state.step(input.next());
+        assert_eq!(state.head(), Square::new(3, 2));
+
+        state.step(input.next());
+        assert_eq!(state.head(), Square::new(2, 2));
+        assert!(!state.game_over);
+    }
+
+    #[test]
+    fn ignores_reversals_and_repeats() {
+        let state = long_snake();
+        let mut input = InputQueue::new();
+
+        input.push(Direction::Left, &state);
+        input.push(Direction::Right, &state);
+        assert_eq!(input.next(), None);
+
+        input.push(Direction::Up, &state);
+        input.push(Direction::Down, &state);
+        assert_eq!(input.next(), Some(Direction::Up));
+        assert_eq!(input.next(), None);
+    }
+
+    #[test]
+    fn drops_presses_past_the_limit() {
+        let state = long_snake();
+        let mut input = InputQueue::new();
+
+        for dir in [Direction::Up, Direction::Left, Direction::Down, Direction::Right] {
+            input.push(dir, &state);
+        }
+
+        assert_eq!(input.next(), Some(Direction::Up));
+        assert_eq!(input.next(), Some(Direction::Left));
+        assert_eq!(input.next(), Some(Direction::Down));
+        assert_eq!(input.next(), None);
+    }
+}
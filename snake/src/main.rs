@@ -1,159 +1,226 @@
-use macroquad::prelude::*;
+mod autopilot;
+mod input;
+mod scores;
+mod state;
 
-const SQUARE_SIZE: f32 = 30.0;
-const SPEED: f32 = 1.0;
-const DELAY: f64 = 0.3;
+use macroquad::prelude::*;
 
-struct Square {
-    x: f32,
-    y: f32,
-}
+use crate::{input::InputQueue, scores::HighScores, state::{Direction, SnakeState, WallMode}};
 
-impl Square {
-    pub fn new(x: f32, y: f32) -> Square {
-        Square { x: x, y: y }
-    }
-}
+const SQUARE_SIZE: f32 = 30.0;
 
 struct Game {
-    pub dir_x: f32,
-    pub dir_y: f32,
-    pub food: Square,
-    pub snake: Vec<Square>,
+    pub state: SnakeState,
+    pub input: InputQueue,
+    pub scores: HighScores,
+    pub new_record: bool,
+    pub autopilot: bool,
+    recorded: bool,
 }
 
 impl Game {
-    pub fn new() -> Game {
-        let first = Square::new(0.0, 0.0);
+    pub fn new(mode: WallMode) -> Game {
+        let seed = miniquad::date::now() as u64;
+        let width = (screen_width() / SQUARE_SIZE) as i32;
+        let height = (screen_height() / SQUARE_SIZE) as i32;
 
         Game {
-            snake: vec![first],
-            dir_x: 1.0,
-            dir_y: 0.0,
-            food: Square::new(5.0, 5.0)
+            state: SnakeState::new(width, height, mode, seed),
+            input: InputQueue::new(),
+            scores: HighScores::load(),
+            new_record: false,
+            autopilot: false,
+            recorded: false,
         }
     }
 
     pub fn mov(&mut self) {
-        self.log();
-        self.move_snake();
-        self.eat();
+        if self.autopilot {
+            self.drive();
+        }
+
+        self.state.step(self.input.next());
+
+        if self.state.is_finished() && !self.recorded {
+            self.record_score();
+        }
+    }
+
+    pub fn record_score(&mut self) {
+        self.recorded = true;
+        self.new_record = self.scores.add(self.state.score);
+
+        if self.new_record {
+            self.scores.save();
+        }
     }
 
     pub fn right(&mut self) {
-        self.dir_x = SPEED;
-        self.dir_y = 0.0;
+        if !self.autopilot {
+            self.input.push(Direction::Right, &self.state);
+        }
     }
 
     pub fn left(&mut self) {
-        self.dir_x = SPEED * -1.0;
-        self.dir_y = 0.0;
+        if !self.autopilot {
+            self.input.push(Direction::Left, &self.state);
+        }
     }
 
     pub fn up(&mut self) {
-        self.dir_x = 0.0;
-        self.dir_y = SPEED*-1.0;
+        if !self.autopilot {
+            self.input.push(Direction::Up, &self.state);
+        }
     }
 
     pub fn down(&mut self) {
-        self.dir_x = 0.0;
-        self.dir_y = SPEED;
+        if !self.autopilot {
+            self.input.push(Direction::Down, &self.state);
+        }
     }
 
-    pub fn draw(&mut self) {
-        self.draw_snake();
-        self.draw_food();
+    pub fn drive(&mut self) {
+        if let Some(dir) = autopilot::next_direction(&self.state) {
+            self.input.push(dir, &self.state);
+        }
     }
 
-    pub fn draw_food(&self) {
-        self.draw_square(self.food.x, self.food.y);
+    pub fn toggle_autopilot(&mut self) {
+        self.autopilot = !self.autopilot;
+        self.input = InputQueue::new();
     }
 
-    pub fn move_snake(&mut self) {
-        let mut i = self.snake.len() -1;
+    pub fn toggle_mode(&mut self) {
+        self.state.mode = self.state.mode.toggle();
+    }
 
-        while i >= 1 {
-            self.snake[i].x = self.snake[i-1].x;
-            self.snake[i].y = self.snake[i-1].y;
-            i -= 1;
+    pub fn draw(&self) {
+        self.draw_snake();
+        self.draw_food();
+        self.draw_mode();
+        self.draw_score();
+
+        if self.state.game_over {
+            self.show_game_over();
+        }
+
+        if self.state.won {
+            self.show_win();
         }
-        self.snake[0].x += self.dir_x;
-        self.snake[0].y += self.dir_y;
     }
 
-    pub fn draw_snake(&mut self) {
-        for s in &self.snake {
+    pub fn draw_food(&self) {
+        let food = self.state.food;
+        self.draw_square(food.x, food.y);
+    }
+
+    pub fn draw_snake(&self) {
+        for s in &self.state.snake {
             self.draw_square(s.x, s.y);
         }
     }
 
-    pub fn draw_square(&self, x: f32, y: f32) {
+    pub fn draw_square(&self, x: i32, y: i32) {
         draw_rectangle(
-            x * SQUARE_SIZE,
-            y * SQUARE_SIZE,
+            x as f32 * SQUARE_SIZE,
+            y as f32 * SQUARE_SIZE,
             SQUARE_SIZE,
             SQUARE_SIZE,
             WHITE,
         );
     }
 
-    pub fn log(&self) {
-        let head = &self.snake[0];
-        println!("HEAD ({}, {})", head.x, head.y);
+    pub fn draw_mode(&self) {
+        draw_text(self.state.mode.name(), 10., screen_height() - 10., 20., GRAY);
+
+        if self.autopilot {
+            draw_text("AUTOPILOT (A)", 160., screen_height() - 10., 20., GREEN);
+        }
     }
 
-    pub fn eat(&mut self) {
-        let head = &self.snake[0];
-        let next_head = Square {
-            x:  head.x+self.dir_x,
-            y:  head.y+self.dir_y
-        };
+    pub fn draw_score(&self) {
+        let text = format!("SCORE: {}", self.state.score);
+        draw_text(&text, 10., 30., 30., YELLOW);
+    }
 
-        let food = &self.food;
+    pub fn show_game_over(&self) {
+        draw_text("GAME OVER", screen_width() / 2. - 100., screen_height() / 2., 40., RED);
+        self.show_restart();
+    }
 
-        let eated = next_head.x == food.x && next_head.y == food.y;
+    pub fn show_win(&self) {
+        draw_text("YOU WIN", screen_width() / 2. - 80., screen_height() / 2., 40., GREEN);
+        self.show_restart();
+    }
 
-        if !eated {
-            return;
-        }
-        
-        println!("Eaten!");
-        self.snake.insert(0, Square {x: food.x, y: food.y});
+    pub fn show_restart(&self) {
+        draw_text(
+            "R: restart   M: change walls",
+            screen_width() / 2. - 130.,
+            screen_height() / 2. + 40.,
+            20.,
+            WHITE,
+        );
+
+        self.show_high_scores();
+    }
 
-        let fx = rand::gen_range(1, 20) as f32;
-        let fy = rand::gen_range(1, 20) as f32;
+    pub fn show_high_scores(&self) {
+        let x = screen_width() / 2. - 60.;
+        let mut y = screen_height() / 2. + 75.;
 
-        self.food.x = fx;
-        self.food.y = fy;
+        let title = if self.new_record { "NEW HIGH SCORE!" } else { "HIGH SCORES" };
+        draw_text(title, x, y, 25., YELLOW);
+
+        for (i, score) in self.scores.scores.iter().enumerate() {
+            y += 20.;
+            let text = format!("{:>2}. {}", i + 1, score);
+            draw_text(&text, x, y, 20., WHITE);
+        }
     }
 }
 
 #[macroquad::main("MyGame")]
 async fn main() {
-    let mut game = Game::new();
+    let mut game = Game::new(WallMode::Kill);
     let mut start_timer = get_time();
     loop {
 
         clear_background(BLACK);
         game.draw();
 
-        if is_key_released(KeyCode::Right) {
+        if is_key_pressed(KeyCode::Right) {
             game.right();
         }
 
-        if is_key_released(KeyCode::Left) {
+        if is_key_pressed(KeyCode::Left) {
             game.left();
         }
 
-        if is_key_released(KeyCode::Up) {
+        if is_key_pressed(KeyCode::Up) {
             game.up();
         }
 
-        if is_key_released(KeyCode::Down) {
+        if is_key_pressed(KeyCode::Down) {
             game.down();
         }
 
-        if get_time() - start_timer > DELAY {
+        if is_key_pressed(KeyCode::M) {
+            game.toggle_mode();
+        }
+
+        if is_key_pressed(KeyCode::A) {
+            game.toggle_autopilot();
+        }
+
+        if game.state.is_finished() && is_key_pressed(KeyCode::R) {
+            let autopilot = game.autopilot;
+            game = Game::new(game.state.mode);
+            game.autopilot = autopilot;
+            start_timer = get_time();
+        }
+
+        if get_time() - start_timer > game.state.delay() {
             start_timer = get_time();
             game.mov();
         }
@@ -0,0 +1,119 @@
+use std::{fs, path::PathBuf};
+
+const MAX_SCORES: usize = 10;
+const FILE_NAME: &str = "highscores.txt";
+
+/// Best scores, highest first, stored one per line in the user's data dir.
+pub struct HighScores {
+    pub scores: Vec<u32>,
+}
+
+impl HighScores {
+    pub fn load() -> HighScores {
+        let text = HighScores::path()
+            .and_then(|path| fs::read_to_string(path).ok())
+            .unwrap_or_default();
+
+        HighScores::parse(&text)
+    }
+
+    pub fn save(&self) {
+        let Some(path) = HighScores::path() else {
+            return;
+        };
+
+        if let Some(dir) = path.parent() {
+            let _ = fs::create_dir_all(dir);
+        }
+
+        if let Err(e) = fs::write(&path, self.format()) {
+            println!("Could not save high scores to {}: {}", path.display(), e);
+        }
+    }
+
+    pub fn path() -> Option<PathBuf> {
+        dirs::data_dir().map(|dir| dir.join("snake").join(FILE_NAME))
+    }
+
+    pub fn parse(text: &str) -> HighScores {
+        let mut scores: Vec<u32> = text
+            .lines()
+            .filter_map(|line| line.trim().parse().ok())
+            .collect();
+
+        scores.sort_by(|a, b| b.cmp(a));
+        scores.truncate(MAX_SCORES);
+
+        HighScores { scores }
+    }
+
+    pub fn format(&self) -> String {
+        self.scores
+            .iter()
+            .map(|s| format!("{}\n", s))
+            .collect()
+    }
+
+    /// Adds a score, returning whether it made it into the table.
+    pub fn add(&mut self, score: u32) -> bool {
+        if score == 0 {
+            return false;
+        }
+
+        let i = self.scores
+            .iter()
+            .position(|s| score > *s)
+            .unwrap_or(self.scores.len());
+
+        if i >= MAX_SCORES {
+            return false;
+        }
+
+        self.scores.insert(i, score);
+        self.scores.truncate(MAX_SCORES);
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_sorts_and_skips_garbage() {
+        let scores = HighScores::parse("30\nnope\n\n50\n 10 \n");
+
+        assert_eq!(scores.scores, vec![50, 30, 10]);
+    }
+
+    #[test]
+    fn format_round_trips() {
+        let scores = HighScores::parse("20\n40\n");
+
+        assert_eq!(HighScores::parse(&scores.format()).scores, vec![40, 20]);
+    }
+
+    #[test]
+    fn add_keeps_the_best_ten() {
+        let mut scores = HighScores::parse("");
+
+        for s in 1..=10 {
+            assert!(scores.add(s * 10));
+        }
+
+        assert!(!scores.add(5));
+        assert!(scores.add(55));
+        assert_eq!(scores.scores.len(), MAX_SCORES);
+        assert_eq!(scores.scores[0], 100);
+        assert_eq!(scores.scores[5], 55);
+        assert_eq!(*scores.scores.last().unwrap(), 20);
+    }
+
+    #[test]
+    fn zero_is_not_a_high_score() {
+        let mut scores = HighScores::parse("");
+
+        assert!(!scores.add(0));
+        assert!(scores.scores.is_empty());
+    }
+}
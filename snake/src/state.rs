@@ -0,0 +1,401 @@
+use macroquad::rand::RandGenerator;
+
+const FOOD_POINTS: u32 = 10;
+const BASE_DELAY: f64 = 0.3;
+const MIN_DELAY: f64 = 0.08;
+const DELAY_STEP: f64 = 0.01;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Direction {
+    Up,
+    Down,
+    Left,
+    Right,
+}
+
+impl Direction {
+    pub fn delta(self) -> (i32, i32) {
+        match self {
+            Direction::Up => (0, -1),
+            Direction::Down => (0, 1),
+            Direction::Left => (-1, 0),
+            Direction::Right => (1, 0),
+        }
+    }
+
+    pub fn opposite(self) -> Direction {
+        match self {
+            Direction::Up => Direction::Down,
+            Direction::Down => Direction::Up,
+            Direction::Left => Direction::Right,
+            Direction::Right => Direction::Left,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum WallMode {
+    Kill,
+    Wrap,
+}
+
+impl WallMode {
+    pub fn toggle(self) -> WallMode {
+        match self {
+            WallMode::Kill => WallMode::Wrap,
+            WallMode::Wrap => WallMode::Kill,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            WallMode::Kill => "WALLS KILL",
+            WallMode::Wrap => "WRAP AROUND",
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Square {
+    pub x: i32,
+    pub y: i32,
+}
+
+impl Square {
+    pub fn new(x: i32, y: i32) -> Square {
+        Square { x, y }
+    }
+}
+
+/// Everything snake needs to play a game, without a window.
+/// The head is the first square of `snake`.
+pub struct SnakeState {
+    pub snake: Vec<Square>,
+    pub dir: Direction,
+    pub food: Square,
+    pub mode: WallMode,
+    pub game_over: bool,
+    pub won: bool,
+    pub score: u32,
+    pub width: i32,
+    pub height: i32,
+    rng: RandGenerator,
+}
+
+impl SnakeState {
+    pub fn new(width: i32, height: i32, mode: WallMode, seed: u64) -> SnakeState {
+        let rng = RandGenerator::new();
+        rng.srand(seed);
+
+        let mut state = SnakeState {
+            snake: vec![Square::new(0, 0)],
+            dir: Direction::Right,
+            food: Square::new(0, 0),
+            mode,
+            game_over: false,
+            won: false,
+            score: 0,
+            width,
+            height,
+            rng,
+        };
+
+        state.place_food();
+        state
+    }
+
+    pub fn head(&self) -> Square {
+        self.snake[0]
+    }
+
+    /// Advances the game one tick, turning first if `input` is a valid turn.
+    pub fn step(&mut self, input: Option<Direction>) {
+        if self.is_finished() {
+            return;
+        }
+
+        if let Some(dir) = input {
+            self.turn(dir);
+        }
+
+        let next = match self.next_head() {
+            Some(next) => next,
+            None => {
+                self.game_over = true;
+                return;
+            }
+        };
+
+        let eating = next == self.food;
+
+        // The tail moves away this tick unless the snake grows.
+        let body_len = if eating {
+            self.snake.len()
+        } else {
+            self.snake.len() - 1
+        };
+
+        if self.snake[..body_len].contains(&next) {
+            self.game_over = true;
+            return;
+        }
+
+        self.snake.insert(0, next);
+
+        if eating {
+            self.score += FOOD_POINTS;
+            self.place_food();
+        } else {
+            self.snake.pop();
+        }
+    }
+
+    pub fn turn(&mut self, dir: Direction) {
+        if self.snake.len() > 1 && dir == self.dir.opposite() {
+            return;
+        }
+
+        self.dir = dir;
+    }
+
+    pub fn next_head(&self) -> Option<Square> {
+        let head = self.head();
+        let (dx, dy) = self.dir.delta();
+        let x = head.x + dx;
+        let y = head.y + dy;
+
+        if self.is_inside(x, y) {
+            return Some(Square::new(x, y));
+        }
+
+        match self.mode {
+            WallMode::Kill => None,
+            WallMode::Wrap => Some(Square::new(
+                x.rem_euclid(self.width),
+                y.rem_euclid(self.height),
+            )),
+        }
+    }
+
+    /// Seconds between ticks; the snake speeds up as it grows.
+    pub fn delay(&self) -> f64 {
+        let grown = (self.snake.len() - 1) as f64;
+
+        (BASE_DELAY - grown * DELAY_STEP).max(MIN_DELAY)
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.game_over || self.won
+    }
+
+    pub fn is_inside(&self, x: i32, y: i32) -> bool {
+        x >= 0 && y >= 0 && x < self.width && y < self.height
+    }
+
+    pub fn free_cells(&self) -> Vec<Square> {
+        let mut cells = vec![];
+
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let cell = Square::new(x, y);
+
+                if !self.snake.contains(&cell) {
+                    cells.push(cell);
+                }
+            }
+        }
+
+        cells
+    }
+
+    /// Puts the food on a random free cell, or wins the game when the
+    /// snake fills the whole board.
+    pub fn place_food(&mut self) {
+        let cells = self.free_cells();
+
+        if cells.is_empty() {
+            self.won = true;
+            return;
+        }
+
+        let i = self.rng.gen_range(0, cells.len());
+        self.food = cells[i];
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn state_with(snake: Vec<Square>, dir: Direction) -> SnakeState {
+        let mut state = SnakeState::new(10, 10, WallMode::Kill, 1);
+        state.snake = snake;
+        state.dir = dir;
+        state.food = Square::new(9, 9);
+        state
+    }
+
+    #[test]
+    fn moves_one_square_per_step() {
+        let mut state = state_with(vec![Square::new(2, 2)], Direction::Right);
+
+        state.step(None);
+        assert_eq!(state.head(), Square::new(3, 2));
+
+        state.step(Some(Direction::Down));
+        assert_eq!(state.head(), Square::new(3, 3));
+        assert_eq!(state.snake.len(), 1);
+    }
+
+    #[test]
+    fn grows_when_eating() {
+        let mut state = state_with(vec![Square::new(2, 2), Square::new(1, 2)], Direction::Right);
+        state.food = Square::new(3, 2);
+
+        state.step(None);
+
+        assert_eq!(state.snake, vec![Square::new(3, 2), Square::new(2, 2), Square::new(1, 2)]);
+        assert_ne!(state.food, Square::new(3, 2));
+        assert_eq!(state.score, FOOD_POINTS);
+    }
+
+    #[test]
+    fn speeds_up_as_it_grows() {
+        let mut state = state_with(vec![Square::new(0, 0)], Direction::Right);
+        assert_eq!(state.delay(), BASE_DELAY);
+
+        state.snake = vec![Square::new(0, 0); 6];
+        assert!(state.delay() < BASE_DELAY);
+
+        state.snake = vec![Square::new(0, 0); 100];
+        assert_eq!(state.delay(), MIN_DELAY);
+    }
+
+    #[test]
+    fn ignores_reversing_into_itself() {
+        let mut state = state_with(vec![Square::new(2, 2), Square::new(1, 2)], Direction::Right);
+
+        state.step(Some(Direction::Left));
+
+        assert!(!state.game_over);
+        assert_eq!(state.dir, Direction::Right);
+        assert_eq!(state.head(), Square::new(3, 2));
+    }
+
+    #[test]
+    fn single_square_can_reverse() {
+        let mut state = state_with(vec![Square::new(2, 2)], Direction::Right);
+
+        state.step(Some(Direction::Left));
+
+        assert_eq!(state.head(), Square::new(1, 2));
+    }
+
+    #[test]
+    fn dies_on_own_body() {
+        let snake = vec![
+            Square::new(2, 2),
+            Square::new(2, 3),
+            Square::new(3, 3),
+            Square::new(3, 2),
+            Square::new(4, 2),
+        ];
+        let mut state = state_with(snake, Direction::Up);
+
+        state.step(Some(Direction::Right));
+
+        assert!(state.game_over);
+    }
+
+    #[test]
+    fn can_follow_its_own_tail() {
+        let snake = vec![
+            Square::new(2, 2),
+            Square::new(2, 3),
+            Square::new(3, 3),
+            Square::new(3, 2),
+        ];
+        let mut state = state_with(snake, Direction::Up);
+
+        state.step(Some(Direction::Right));
+
+        assert!(!state.game_over);
+        assert_eq!(state.head(), Square::new(3, 2));
+    }
+
+    #[test]
+    fn walls_kill() {
+        let mut state = state_with(vec![Square::new(9, 0)], Direction::Right);
+
+        state.step(None);
+
+        assert!(state.game_over);
+    }
+
+    #[test]
+    fn walls_wrap() {
+        let mut state = state_with(vec![Square::new(9, 0)], Direction::Right);
+        state.mode = WallMode::Wrap;
+
+        state.step(None);
+        assert_eq!(state.head(), Square::new(0, 0));
+
+        state.step(Some(Direction::Up));
+        assert_eq!(state.head(), Square::new(0, 9));
+    }
+
+    #[test]
+    fn food_never_spawns_on_body() {
+        let mut snake = vec![];
+        for y in 0..10 {
+            for x in 0..10 {
+                if (x, y) != (9, 9) {
+                    snake.push(Square::new(x, y));
+                }
+            }
+        }
+        let mut state = state_with(snake, Direction::Right);
+
+        for _ in 0..20 {
+            state.place_food();
+            assert_eq!(state.food, Square::new(9, 9));
+        }
+    }
+
+    #[test]
+    fn wins_when_board_is_full() {
+        let mut state = SnakeState::new(2, 2, WallMode::Kill, 1);
+        state.snake = vec![Square::new(1, 0), Square::new(0, 0), Square::new(0, 1)];
+        state.dir = Direction::Down;
+        state.food = Square::new(1, 1);
+
+        state.step(None);
+
+        assert!(state.won);
+        assert!(!state.game_over);
+        assert_eq!(state.snake.len(), 4);
+
+        state.step(None);
+        assert_eq!(state.head(), Square::new(1, 1));
+    }
+
+    #[test]
+    fn free_cells_skip_body() {
+        let mut state = SnakeState::new(3, 1, WallMode::Kill, 1);
+        state.snake = vec![Square::new(1, 0)];
+
+        assert_eq!(state.free_cells(), vec![Square::new(0, 0), Square::new(2, 0)]);
+    }
+
+    #[test]
+    fn same_seed_same_food() {
+        let mut a = SnakeState::new(20, 20, WallMode::Kill, 42);
+        let mut b = SnakeState::new(20, 20, WallMode::Kill, 42);
+
+        for _ in 0..10 {
+            assert_eq!(a.food, b.food);
+            a.place_food();
+            b.place_food();
+        }
+    }
+}
@@ -0,0 +1,76 @@
+//! A Tiled JSON map read straight with nanoserde, for the bits
+//! macroquad_tiled doesn't keep and for reading a map without a window or
+//! textures. Fields a map leaves out get their defaults.
+// The code DeJson derives trips clippy.
+#![allow(clippy::question_mark)]
+
+use nanoserde::DeJson;
+
+#[derive(DeJson, Default)]
+#[nserde(default)]
+pub struct MapInfo {
+    /// Size in tiles.
+    pub width: usize,
+    pub height: usize,
+    pub tilewidth: f32,
+    pub tileheight: f32,
+    pub backgroundcolor: Option<String>,
+    pub layers: Vec<LayerInfo>,
+}
+
+#[derive(DeJson, Default)]
+#[nserde(default)]
+pub struct LayerInfo {
+    pub name: String,
+    #[nserde(rename = "type")]
+    pub ty: String,
+    pub visible: Option<bool>,
+    /// The tiles of a tile layer, row by row. 0 is no tile.
+    pub data: Vec<u32>,
+    pub image: Option<String>,
+    pub offsetx: Option<f32>,
+    pub offsety: Option<f32>,
+    pub parallaxx: Option<f32>,
+    pub parallaxy: Option<f32>,
+    pub repeatx: Option<bool>,
+}
+
+impl MapInfo {
+    pub fn layer(&self, name: &str) -> Option<&LayerInfo> {
+        self.layers.iter().find(|layer| layer.name == name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_layers_and_defaults_what_is_missing() {
+        let json = r#"{
+            "width": 2, "height": 1, "tilewidth": 16, "tileheight": 16,
+            "layers": [
+                { "name": "ground", "type": "tilelayer", "data": [1, 0] },
+                { "name": "sky", "type": "imagelayer", "image": "sky.png", "parallaxx": 0.5, "visible": false }
+            ]
+        }"#;
+
+        let map: MapInfo = DeJson::deserialize_json(json).unwrap();
+        assert_eq!((map.width, map.height, map.tilewidth, map.tileheight), (2, 1, 16., 16.));
+        assert_eq!(map.backgroundcolor, None);
+
+        let ground = map.layer("ground").unwrap();
+        assert_eq!(ground.ty, "tilelayer");
+        assert_eq!(ground.data, [1, 0]);
+        assert_eq!(ground.image, None);
+
+        let sky = map.layer("sky").unwrap();
+        assert_eq!(sky.ty, "imagelayer");
+        assert!(sky.data.is_empty());
+        assert_eq!(sky.image.as_deref(), Some("sky.png"));
+        assert_eq!(sky.parallaxx, Some(0.5));
+        assert_eq!(sky.visible, Some(false));
+
+        assert!(map.layer("entities").is_none());
+    }
+}